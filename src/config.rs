@@ -0,0 +1,1278 @@
+use crate::errors::RenameError;
+use crate::parser::{unordered_hash, ThethaCoreConfig, Value};
+use indexmap::IndexMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+impl ThethaCoreConfig {
+    /// The names of all sections, sorted lexicographically.
+    ///
+    /// Nested sections appear under their path, e.g. `"database/advanced"`.
+    pub fn sorted_section_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.sections.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Convert into sections and keys held in `BTreeMap`s, so that both iterate
+    /// in lexicographic order. Fields of object values keep their order.
+    pub fn into_sorted(self) -> BTreeMap<String, BTreeMap<String, Value>> {
+        self.sections
+            .into_iter()
+            .map(|(name, section)| (name, section.into_iter().collect()))
+            .collect()
+    }
+
+    /// Borrow the keys of a single section.
+    pub fn section(&self, name: &str) -> Option<&IndexMap<String, Value>> {
+        self.sections.get(name)
+    }
+
+    /// The sections directly under `prefix` whose names are numbers, such as
+    /// `<servers<0>>` and `<servers<1>>` for the prefix `"servers"`, ordered by
+    /// number.
+    ///
+    /// Gaps in the numbering are skipped, and deeper sections such as
+    /// `"servers/0/tls"` are left out.
+    pub fn get_section_array(&self, prefix: &str) -> Vec<&IndexMap<String, Value>> {
+        let mut indexed: Vec<(u64, &IndexMap<String, Value>)> = self
+            .sections
+            .iter()
+            .filter_map(|(name, section)| {
+                let index = name.strip_prefix(prefix)?.strip_prefix('/')?;
+                let is_index = !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit());
+                Some((index.parse().ok().filter(|_| is_index)?, section))
+            })
+            .collect();
+        indexed.sort_by_key(|(index, _)| *index);
+        indexed.into_iter().map(|(_, section)| section).collect()
+    }
+
+    /// Whether a section called `name` exists, even if it has no keys.
+    pub fn contains_section(&self, name: &str) -> bool {
+        self.sections.contains_key(name)
+    }
+
+    /// Whether `section` exists and defines `key`.
+    pub fn contains_key(&self, section: &str, key: &str) -> bool {
+        self.get_value(section, key).is_some()
+    }
+
+    /// Get the raw value stored under `key` in `section`.
+    pub fn get_value(&self, section: &str, key: &str) -> Option<&Value> {
+        self.sections.get(section)?.get(key)
+    }
+
+    /// Get a value like [`get_value`](Self::get_value), but matching section
+    /// and key names without regard to ASCII case.
+    ///
+    /// If several sections or keys differ only by case, which one is used is
+    /// unspecified.
+    pub fn get_ci(&self, section: &str, key: &str) -> Option<&Value> {
+        if let Some(value) = self.get_value(section, key) {
+            return Some(value);
+        }
+        let (_, entries) = self
+            .sections
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(section))?;
+        entries
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    }
+
+    /// Get a string value. Returns `None` if the key is missing or not a string.
+    pub fn get_string(&self, section: &str, key: &str) -> Option<&str> {
+        self.get_value(section, key)?.as_str()
+    }
+
+    /// Get an integer value. Returns `None` if the key is missing or not an integer.
+    pub fn get_i64(&self, section: &str, key: &str) -> Option<i64> {
+        self.get_value(section, key)?.as_i64()
+    }
+
+    /// Get a float value. Returns `None` if the key is missing or not a float.
+    pub fn get_f64(&self, section: &str, key: &str) -> Option<f64> {
+        self.get_value(section, key)?.as_f64()
+    }
+
+    /// Get a boolean value. Returns `None` if the key is missing or not a boolean.
+    pub fn get_bool(&self, section: &str, key: &str) -> Option<bool> {
+        self.get_value(section, key)?.as_bool()
+    }
+
+    /// Get an array of strings. Returns `None` if the key is missing, not an
+    /// array, or holds an element that isn't a string.
+    pub fn get_string_array(&self, section: &str, key: &str) -> Option<Vec<String>> {
+        self.get_array_with(section, key, |v| v.as_str().map(str::to_string))
+    }
+
+    /// Get an array of integers. Returns `None` if the key is missing, not an
+    /// array, or holds an element that isn't an integer.
+    pub fn get_i64_array(&self, section: &str, key: &str) -> Option<Vec<i64>> {
+        self.get_array_with(section, key, Value::as_i64)
+    }
+
+    /// Get an array of floats. Returns `None` if the key is missing, not an
+    /// array, or holds an element that isn't a float.
+    pub fn get_f64_array(&self, section: &str, key: &str) -> Option<Vec<f64>> {
+        self.get_array_with(section, key, Value::as_f64)
+    }
+
+    fn get_array_with<T>(
+        &self,
+        section: &str,
+        key: &str,
+        element: impl Fn(&Value) -> Option<T>,
+    ) -> Option<Vec<T>> {
+        self.get_value(section, key)?
+            .as_array()?
+            .iter()
+            .map(element)
+            .collect()
+    }
+
+    /// Get a clone of the value under `key` in `section`, or `default` if the
+    /// key is missing.
+    pub fn get_or(&self, section: &str, key: &str, default: Value) -> Value {
+        self.get_value(section, key).cloned().unwrap_or(default)
+    }
+
+    /// Get a string value, or `default` if the key is missing or not a string.
+    pub fn get_str_or(&self, section: &str, key: &str, default: &str) -> String {
+        self.get_string(section, key).unwrap_or(default).to_string()
+    }
+
+    /// Get a value by its full path, e.g. `"database/advanced/pool_size"`.
+    ///
+    /// Everything before the last `/` is the section path, the rest is the key.
+    /// Returns `None` if the path contains no `/`.
+    pub fn get_by_path(&self, path: &str) -> Option<&Value> {
+        let (section, key) = path.rsplit_once('/')?;
+        self.get_value(section, key)
+    }
+
+    /// The keys of `section` starting with `prefix`, with their values, in file
+    /// order. Empty if the section doesn't exist.
+    pub fn keys_with_prefix(&self, section: &str, prefix: &str) -> Vec<(&str, &Value)> {
+        self.keys_where(section, |key| key.starts_with(prefix))
+    }
+
+    /// The keys of `section` matching a glob `pattern`, with their values, in
+    /// file order.
+    ///
+    /// `*` matches any run of characters and `?` any single character, so
+    /// `"feature_*"` matches `feature_x` and `feature_` but not `features`.
+    pub fn keys_matching(&self, section: &str, pattern: &str) -> Vec<(&str, &Value)> {
+        let pattern: Vec<char> = pattern.chars().collect();
+        self.keys_where(section, |key| {
+            glob_match(&pattern, &key.chars().collect::<Vec<_>>())
+        })
+    }
+
+    fn keys_where(&self, section: &str, matches: impl Fn(&str) -> bool) -> Vec<(&str, &Value)> {
+        self.sections
+            .get(section)
+            .into_iter()
+            .flatten()
+            .filter(|(key, _)| matches(key))
+            .map(|(key, value)| (key.as_str(), value))
+            .collect()
+    }
+
+    /// Iterate over every `(section, key, value)` entry, in file order.
+    ///
+    /// Nested sections are yielded under their flattened path, e.g. `"database/advanced"`.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &Value)> {
+        self.sections.iter().flat_map(|(section, entries)| {
+            entries
+                .iter()
+                .map(move |(key, value)| (section.as_str(), key.as_str(), value))
+        })
+    }
+
+    /// The number of key-value entries across all sections.
+    ///
+    /// Only keys directly in a section count; the fields of objects and the
+    /// elements of arrays don't.
+    pub fn len(&self) -> usize {
+        self.sections.values().map(IndexMap::len).sum()
+    }
+
+    /// Whether the configuration has no key-value entries. Sections without
+    /// keys may still exist.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The keys of the section called `name`, which is added, empty and after
+    /// the existing sections, if it doesn't exist.
+    pub fn section_entry(&mut self, name: &str) -> &mut IndexMap<String, Value> {
+        self.sections.entry(name.to_string()).or_default()
+    }
+
+    /// Remove `key` from `section`, returning its value if it was there.
+    ///
+    /// The section itself is kept, even if it becomes empty.
+    pub fn remove_key(&mut self, section: &str, key: &str) -> Option<Value> {
+        self.sections.get_mut(section)?.shift_remove(key)
+    }
+
+    /// Remove the section called `name` with all of its keys, returning them.
+    ///
+    /// Nested sections are stored under their own paths, so removing
+    /// `"database"` leaves `"database/advanced"` in place.
+    pub fn remove_section(&mut self, name: &str) -> Option<IndexMap<String, Value>> {
+        self.sections.shift_remove(name)
+    }
+
+    /// A hash of the configuration's contents, for cheaply telling whether it
+    /// changed.
+    ///
+    /// The order of sections, keys and object fields doesn't matter, so configs
+    /// that compare equal hash equally. The hash is stable within a build of
+    /// the crate but shouldn't be persisted.
+    pub fn content_hash(&self) -> u64 {
+        unordered_hash(self.sections.iter().map(|(name, section)| {
+            let mut hasher = DefaultHasher::new();
+            name.hash(&mut hasher);
+            unordered_hash(section.iter()).hash(&mut hasher);
+            hasher.finish()
+        }))
+    }
+
+    /// Move the section `from`, with its keys, to the name `to`, keeping its
+    /// place in the section order.
+    ///
+    /// Fails if there is no section `from` or a section `to` already exists.
+    /// Nested sections such as `"from/inner"` are left in place; use
+    /// [`rename_section_tree`](Self::rename_section_tree) to move them too.
+    pub fn rename_section(&mut self, from: &str, to: &str) -> Result<(), RenameError> {
+        if self.sections.contains_key(to) {
+            return Err(RenameError::AlreadyExists {
+                section: to.to_string(),
+            });
+        }
+        let (index, _, section) =
+            self.sections
+                .shift_remove_full(from)
+                .ok_or_else(|| RenameError::NotFound {
+                    section: from.to_string(),
+                })?;
+        self.sections.shift_insert(index, to.to_string(), section);
+        Ok(())
+    }
+
+    /// Like [`rename_section`](Self::rename_section), but also moves the
+    /// sections nested under `from`, so `"db/replica"` becomes
+    /// `"database/replica"` when renaming `"db"` to `"database"`.
+    ///
+    /// Fails, changing nothing, if neither `from` nor any section under it
+    /// exists, if one of the new names is already taken, or if `to` is `from`
+    /// or nested under it.
+    pub fn rename_section_tree(&mut self, from: &str, to: &str) -> Result<(), RenameError> {
+        if to
+            .strip_prefix(from)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        {
+            return Err(RenameError::IntoItself {
+                from: from.to_string(),
+                to: to.to_string(),
+            });
+        }
+        let renamed = |name: &str| -> Option<String> {
+            let rest = name.strip_prefix(from)?;
+            (rest.is_empty() || rest.starts_with('/')).then(|| format!("{}{}", to, rest))
+        };
+        let mut moved = 0;
+        for name in self.sections.keys() {
+            if let Some(new_name) = renamed(name) {
+                moved += 1;
+                if self.sections.contains_key(&new_name) && renamed(&new_name).is_none() {
+                    return Err(RenameError::AlreadyExists { section: new_name });
+                }
+            }
+        }
+        if moved == 0 {
+            return Err(RenameError::NotFound {
+                section: from.to_string(),
+            });
+        }
+        self.sections = std::mem::take(&mut self.sections)
+            .into_iter()
+            .map(|(name, section)| (renamed(&name).unwrap_or(name), section))
+            .collect();
+        Ok(())
+    }
+
+    /// Merge `other` into this configuration.
+    ///
+    /// Keys from `other` are inserted into the matching section, creating it if
+    /// needed, and overwrite existing keys. When both sides hold a
+    /// `Value::Object`, the objects are merged recursively instead.
+    pub fn merge(&mut self, other: &ThethaCoreConfig) {
+        for (name, other_section) in &other.sections {
+            let section = self.sections.entry(name.clone()).or_default();
+            for (key, value) in other_section {
+                match section.get_mut(key) {
+                    Some(existing) => merge_value(existing, value),
+                    None => {
+                        section.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fill in keys from `defaults` that this configuration doesn't define.
+    ///
+    /// This is [`merge`](Self::merge) with the priorities reversed: missing
+    /// sections and keys are copied over, but existing values are never
+    /// replaced. When both sides hold a `Value::Object`, its missing fields are
+    /// filled in recursively.
+    pub fn apply_defaults(&mut self, defaults: &ThethaCoreConfig) {
+        for (name, default_section) in defaults.sections.iter() {
+            let section = self.sections.entry(name.clone()).or_default();
+            for (key, default) in default_section {
+                match section.get_mut(key) {
+                    Some(existing) => fill_value(existing, default),
+                    None => {
+                        section.insert(key.clone(), default.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Turn objects whose keys are exactly `0` to `n - 1`, in any order, into
+    /// arrays of their values in index order, as legacy files wrote arrays like
+    /// `{ 0 == "a", 1 == "b" }`.
+    ///
+    /// Objects nested in arrays and objects are converted too. Other objects,
+    /// including empty ones and those with gaps or keys such as `01`, are left
+    /// alone.
+    pub fn normalize_indexed_objects(&mut self) {
+        for value in self.sections.values_mut().flat_map(IndexMap::values_mut) {
+            normalize_indexed(value);
+        }
+    }
+
+    /// The changes that turn this configuration into `other`, keyed by
+    /// `"section/key"` path: first keys removed or changed, in this
+    /// configuration's order, then keys added, in `other`'s order.
+    ///
+    /// A key whose value differs is reported once as [`Change::Changed`], even
+    /// if only one field of a nested object changed. Sections without keys
+    /// aren't reported.
+    pub fn diff(&self, other: &ThethaCoreConfig) -> Vec<Change> {
+        let mut changes = Vec::new();
+        for (section, key, value) in self.iter() {
+            let path = format!("{}/{}", section, key);
+            match other.get_value(section, key) {
+                None => changes.push(Change::Removed {
+                    path,
+                    value: value.clone(),
+                }),
+                Some(new) if new != value => changes.push(Change::Changed {
+                    path,
+                    old: value.clone(),
+                    new: new.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (section, key, value) in other.iter() {
+            if !self.contains_key(section, key) {
+                changes.push(Change::Added {
+                    path: format!("{}/{}", section, key),
+                    value: value.clone(),
+                });
+            }
+        }
+        changes
+    }
+}
+
+/// Whether `text` matches a glob `pattern` of literal characters, `*` and `?`.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        Some((&p, rest)) => match text.split_first() {
+            Some((&c, text)) => (p == '?' || p == c) && glob_match(rest, text),
+            None => false,
+        },
+    }
+}
+
+/// Overwrite `target` with `other`, merging recursively when both are objects.
+fn merge_value(target: &mut Value, other: &Value) {
+    match (target, other) {
+        (Value::Object(target), Value::Object(other)) => {
+            for (key, value) in other {
+                match target.get_mut(key) {
+                    Some(existing) => merge_value(existing, value),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (target, other) => *target = other.clone(),
+    }
+}
+
+/// One difference between two configurations, as found by
+/// [`ThethaCoreConfig::diff`]. `path` is the `"section/key"` path of the key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// A key only the new configuration has.
+    Added { path: String, value: Value },
+    /// A key only the old configuration has.
+    Removed { path: String, value: Value },
+    /// A key whose value differs between the two.
+    Changed {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl Change {
+    /// The `"section/key"` path of the key that changed.
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// Fluent construction of a [`ThethaCoreConfig`] in code.
+///
+/// `section` selects the section that following `set` calls write to,
+/// creating it if needed, and returns a [`SectionBuilder`], so keys can't be
+/// set before a section is chosen. Nested sections are named by their path,
+/// e.g. `"database/advanced"`.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    config: ThethaCoreConfig,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start writing to `name`. Reselecting an earlier section keeps its keys.
+    pub fn section(mut self, name: &str) -> SectionBuilder {
+        self.config.sections.entry(name.to_string()).or_default();
+        SectionBuilder {
+            config: self.config,
+            current: name.to_string(),
+        }
+    }
+
+    pub fn build(self) -> ThethaCoreConfig {
+        self.config
+    }
+}
+
+/// A [`ConfigBuilder`] with a section selected, which `set` writes to.
+#[derive(Debug, Clone)]
+pub struct SectionBuilder {
+    config: ThethaCoreConfig,
+    current: String,
+}
+
+impl SectionBuilder {
+    /// Start writing to `name` instead. Reselecting an earlier section keeps
+    /// its keys.
+    pub fn section(self, name: &str) -> SectionBuilder {
+        ConfigBuilder {
+            config: self.config,
+        }
+        .section(name)
+    }
+
+    /// Set `key` in the current section, replacing any earlier value.
+    pub fn set(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.config.sections[&self.current].insert(key.to_string(), value.into());
+        self
+    }
+
+    pub fn build(self) -> ThethaCoreConfig {
+        self.config
+    }
+}
+
+/// Add fields of `default` missing from `target` when both are objects.
+fn fill_value(target: &mut Value, default: &Value) {
+    if let (Value::Object(target), Value::Object(default)) = (target, default) {
+        for (key, value) in default {
+            match target.get_mut(key) {
+                Some(existing) => fill_value(existing, value),
+                None => {
+                    target.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Convert `value` and the values inside it for
+/// [`ThethaCoreConfig::normalize_indexed_objects`], innermost first.
+fn normalize_indexed(value: &mut Value) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(normalize_indexed),
+        Value::Object(fields) => {
+            fields.values_mut().for_each(normalize_indexed);
+            let indexed = !fields.is_empty()
+                && (0..fields.len()).all(|i| fields.contains_key(i.to_string().as_str()));
+            if indexed {
+                let mut fields = std::mem::take(fields);
+                let items = (0..fields.len())
+                    .map(|i| fields.swap_remove(i.to_string().as_str()).unwrap())
+                    .collect();
+                *value = Value::Array(items);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_accessors() {
+        let input = r#"
+        <general>
+        app_name == "TestApp"
+        version == 1.5
+        retries == 3
+        enabled == True
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.get_string("general", "app_name"), Some("TestApp"));
+        assert_eq!(config.get_f64("general", "version"), Some(1.5));
+        assert_eq!(config.get_i64("general", "retries"), Some(3));
+        assert_eq!(config.get_bool("general", "enabled"), Some(true));
+        assert_eq!(
+            config.get_value("general", "retries"),
+            Some(&Value::Integer(3))
+        );
+    }
+
+    #[test]
+    fn test_typed_accessors_missing_or_mismatched() {
+        let input = r#"
+        <general>
+        app_name == "TestApp"
+        retries == 3
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.get_string("general", "missing"), None);
+        assert_eq!(config.get_string("missing", "app_name"), None);
+        assert_eq!(config.get_i64("general", "app_name"), None);
+        assert_eq!(config.get_f64("general", "retries"), None);
+        assert_eq!(config.get_bool("general", "retries"), None);
+    }
+
+    #[test]
+    fn test_typed_array_accessors() {
+        let input = r#"
+        <lists>
+        names == ["a", "b"]
+        ports == [80, 443]
+        ratios == [0.5, 1.5]
+        mixed == ["a", 1]
+        empty == []
+        single == "a"
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.get_string_array("lists", "names"),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(config.get_i64_array("lists", "ports"), Some(vec![80, 443]));
+        assert_eq!(
+            config.get_f64_array("lists", "ratios"),
+            Some(vec![0.5, 1.5])
+        );
+        assert_eq!(config.get_string_array("lists", "empty"), Some(vec![]));
+
+        assert_eq!(config.get_string_array("lists", "mixed"), None);
+        assert_eq!(config.get_i64_array("lists", "mixed"), None);
+        assert_eq!(config.get_f64_array("lists", "ports"), None);
+        assert_eq!(config.get_string_array("lists", "single"), None);
+        assert_eq!(config.get_string_array("lists", "missing"), None);
+    }
+
+    #[test]
+    fn test_sorted_section_names() {
+        let input = r#"
+        <zeta>
+        a == 1
+
+        <alpha<beta>>
+        b == 2
+
+        <alpha>
+        c == 3
+        "#;
+        let first = ThethaCoreConfig::parse(input).unwrap();
+        let second = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            first.sorted_section_names(),
+            vec!["alpha", "alpha/beta", "zeta"]
+        );
+        assert_eq!(first.sorted_section_names(), second.sorted_section_names());
+
+        let alpha = first.section("alpha").unwrap();
+        assert_eq!(alpha.get("c"), Some(&Value::Integer(3)));
+        assert!(first.section("missing").is_none());
+    }
+
+    #[test]
+    fn test_get_or() {
+        let input = r#"
+        <general>
+        app_name == "TestApp"
+        retries == 3
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.get_or("general", "retries", Value::Integer(1)),
+            Value::Integer(3)
+        );
+        assert_eq!(
+            config.get_or("general", "timeout", Value::Integer(30)),
+            Value::Integer(30)
+        );
+        assert_eq!(
+            config.get_or("missing", "retries", Value::Null),
+            Value::Null
+        );
+
+        assert_eq!(
+            config.get_str_or("general", "app_name", "default"),
+            "TestApp"
+        );
+        assert_eq!(
+            config.get_str_or("general", "missing", "default"),
+            "default"
+        );
+        assert_eq!(
+            config.get_str_or("general", "retries", "default"),
+            "default"
+        );
+    }
+
+    #[test]
+    fn test_get_ci() {
+        let input = r#"
+        <General>
+        AppName == "TestApp"
+
+        <Database<Advanced>>
+        pool_SIZE == 10
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.get_ci("general", "appname"),
+            Some(&Value::String("TestApp".to_string()))
+        );
+        assert_eq!(
+            config.get_ci("GENERAL", "APPNAME"),
+            config.get_value("General", "AppName")
+        );
+        assert_eq!(
+            config.get_ci("database/advanced", "Pool_Size"),
+            Some(&Value::Integer(10))
+        );
+        assert_eq!(config.get_value("general", "appname"), None);
+        assert_eq!(config.get_ci("general", "missing"), None);
+        assert_eq!(config.get_ci("missing", "appname"), None);
+    }
+
+    #[test]
+    fn test_get_section_array() {
+        let input = r#"
+        <servers<10>>
+        host == "c"
+        <servers<2>>
+        host == "b"
+        <servers>
+        count == 3
+        <servers<0>>
+        host == "a"
+        <servers<0<tls>>
+        cert == "x"
+        <servers<primary>>
+        host == "p"
+        <servers<+1>>
+        host == "q"
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let hosts: Vec<&str> = config
+            .get_section_array("servers")
+            .iter()
+            .map(|section| section.get("host").and_then(Value::as_str).unwrap())
+            .collect();
+        assert_eq!(hosts, ["a", "b", "c"]);
+        assert!(config.get_section_array("missing").is_empty());
+        assert_eq!(config.get_section_array("servers/0").len(), 0);
+    }
+
+    #[test]
+    fn test_contains() {
+        let input = r#"
+        <general>
+        app_name == "TestApp"
+
+        <database<advanced>>
+        pool_size == 10
+
+        <empty>
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert!(config.contains_section("general"));
+        assert!(config.contains_section("database/advanced"));
+        assert!(config.contains_section("empty"));
+        assert!(!config.contains_section("database"));
+        assert!(!config.contains_section("missing"));
+
+        assert!(config.contains_key("general", "app_name"));
+        assert!(config.contains_key("database/advanced", "pool_size"));
+        assert!(!config.contains_key("database", "pool_size"));
+        assert!(!config.contains_key("general", "missing"));
+        assert!(!config.contains_key("missing", "app_name"));
+    }
+
+    #[test]
+    fn test_remove() {
+        let input = r#"
+        <general>
+        app_name == "TestApp"
+        debug == False
+
+        <database>
+        host == "localhost"
+
+        <database<advanced>>
+        pool_size == 10
+        "#;
+        let mut config = ThethaCoreConfig::parse(input).unwrap();
+
+        assert_eq!(
+            config.remove_key("general", "debug"),
+            Some(Value::Boolean(false))
+        );
+        assert_eq!(config.get_value("general", "debug"), None);
+        assert_eq!(config.remove_key("general", "debug"), None);
+        assert_eq!(config.remove_key("missing", "debug"), None);
+        assert!(config.contains_key("general", "app_name"));
+
+        let removed = config.remove_section("database").unwrap();
+        assert_eq!(
+            removed.get("host"),
+            Some(&Value::String("localhost".to_string()))
+        );
+        assert!(!config.contains_section("database"));
+        assert_eq!(config.get_i64("database/advanced", "pool_size"), Some(10));
+        assert!(config.remove_section("database").is_none());
+        assert_eq!(
+            config.sorted_section_names(),
+            vec!["database/advanced", "general"]
+        );
+    }
+
+    #[test]
+    fn test_get_by_path() {
+        let input = r#"
+        <general>
+        app_name == "TestApp"
+
+        <database<advanced>>
+        pool_size == 10
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.get_by_path("database/advanced/pool_size"),
+            Some(&Value::Integer(10))
+        );
+        assert_eq!(
+            config.get_by_path("general/app_name"),
+            Some(&Value::String("TestApp".to_string()))
+        );
+        assert_eq!(config.get_by_path("database/advanced/missing"), None);
+        assert_eq!(config.get_by_path("database/pool_size"), None);
+        assert_eq!(config.get_by_path("app_name"), None);
+        assert_eq!(config.get_by_path(""), None);
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let a = ThethaCoreConfig::parse(
+            "<one>\nx == 1\ny == { \"a\" == [1, 2], \"b\" == 0.0 }\n<two>\nz == 5s",
+        )
+        .unwrap();
+        let b = ThethaCoreConfig::parse(
+            "<two>\nz == 5s\n<one>\ny == { \"b\" == -0.0, \"a\" == [1, 2] }\nx == 1",
+        )
+        .unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        let changes = [
+            "<one>\nx == 2\ny == { \"a\" == [1, 2], \"b\" == 0.0 }\n<two>\nz == 5s",
+            "<one>\nx == 1\ny == { \"a\" == [2, 1], \"b\" == 0.0 }\n<two>\nz == 5s",
+            "<one>\nx == 1\ny == { \"a\" == [1, 2], \"b\" == 0.0 }\n<two>\nw == 5s",
+            "<one>\nx == 1\ny == { \"a\" == [1, 2], \"b\" == 0.0 }\n<three>\nz == 5s",
+            "<one>\nx == \"1\"\ny == { \"a\" == [1, 2], \"b\" == 0.0 }\n<two>\nz == 5s",
+        ];
+        for input in changes {
+            let changed = ThethaCoreConfig::parse(input).unwrap();
+            assert_ne!(a.content_hash(), changed.content_hash(), "{}", input);
+        }
+        assert_eq!(
+            ThethaCoreConfig::new().content_hash(),
+            ThethaCoreConfig::new().content_hash()
+        );
+    }
+
+    #[test]
+    fn test_rename_section() {
+        let input = "<first>\n<db>\nhost == \"h\"\n<db<replica>>\nport == 1\n<last>";
+        let mut config = ThethaCoreConfig::parse(input).unwrap();
+        config.rename_section("db", "database").unwrap();
+        let names: Vec<&str> = config.sections.keys().map(String::as_str).collect();
+        assert_eq!(names, ["first", "database", "db/replica", "last"]);
+        assert_eq!(
+            config.get_value("database", "host"),
+            Some(&Value::String("h".to_string()))
+        );
+
+        let err = config.rename_section("missing", "other").unwrap_err();
+        assert_eq!(
+            err,
+            RenameError::NotFound {
+                section: "missing".to_string()
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "❌ Error: Section 'missing' does not exist"
+        );
+        let err = config.rename_section("first", "last").unwrap_err();
+        assert_eq!(
+            err,
+            RenameError::AlreadyExists {
+                section: "last".to_string()
+            }
+        );
+        assert_eq!(err.to_string(), "❌ Error: Section 'last' already exists");
+        assert!(config.contains_section("first"));
+    }
+
+    #[test]
+    fn test_rename_section_tree() {
+        let input = "<db>\nhost == \"h\"\n<db<replica>>\nport == 1\n<dbx>\n<other>";
+        let mut config = ThethaCoreConfig::parse(input).unwrap();
+        config.rename_section_tree("db", "database").unwrap();
+        let names: Vec<&str> = config.sections.keys().map(String::as_str).collect();
+        assert_eq!(names, ["database", "database/replica", "dbx", "other"]);
+        assert_eq!(
+            config.get_value("database/replica", "port"),
+            Some(&Value::Integer(1))
+        );
+
+        assert_eq!(
+            config.rename_section_tree("database", "other"),
+            Err(RenameError::AlreadyExists {
+                section: "other".to_string()
+            })
+        );
+        assert_eq!(
+            config.rename_section_tree("db", "x"),
+            Err(RenameError::NotFound {
+                section: "db".to_string()
+            })
+        );
+        assert!(config.contains_section("database/replica"));
+
+        // A tree can't move into itself, but may move to a sibling sharing a
+        // prefix.
+        let before = config.clone();
+        for to in ["database", "database/replica", "database/r/s"] {
+            let err = config.rename_section_tree("database", to).unwrap_err();
+            assert_eq!(
+                err,
+                RenameError::IntoItself {
+                    from: "database".to_string(),
+                    to: to.to_string()
+                }
+            );
+        }
+        assert_eq!(
+            config
+                .rename_section_tree("database", "database/r")
+                .unwrap_err()
+                .to_string(),
+            "❌ Error: Cannot move section 'database' to 'database/r', inside itself"
+        );
+        assert_eq!(config, before);
+        config.rename_section_tree("database", "databases").unwrap();
+        let names: Vec<&str> = config.sections.keys().map(String::as_str).collect();
+        assert_eq!(names, ["databases", "databases/replica", "dbx", "other"]);
+    }
+
+    #[test]
+    fn test_merge_overlay() {
+        let base = ThethaCoreConfig::parse(
+            r#"
+            <database>
+            host == "localhost"
+            port == 5432
+            options == { "pool" == 5, "ssl" == False }
+            "#,
+        )
+        .unwrap();
+        let overlay = ThethaCoreConfig::parse(
+            r#"
+            <database>
+            host == "db.prod"
+            options == { "ssl" == True }
+
+            <cache>
+            ttl == 60
+            "#,
+        )
+        .unwrap();
+
+        let mut config = base.clone();
+        config.merge(&overlay);
+        assert_eq!(config.get_string("database", "host"), Some("db.prod"));
+        assert_eq!(config.get_i64("database", "port"), Some(5432));
+        assert_eq!(config.get_i64("cache", "ttl"), Some(60));
+        if let Some(Value::Object(options)) = config.get_value("database", "options") {
+            assert_eq!(options.get("pool"), Some(&Value::Integer(5)));
+            assert_eq!(options.get("ssl"), Some(&Value::Boolean(true)));
+        } else {
+            panic!("Expected merged object");
+        }
+    }
+
+    #[test]
+    fn test_apply_defaults() {
+        let mut config = ThethaCoreConfig::parse(
+            r#"
+            <database>
+            host == "db.prod"
+            options == { "ssl" == True }
+            "#,
+        )
+        .unwrap();
+        let defaults = ThethaCoreConfig::parse(
+            r#"
+            <database>
+            host == "localhost"
+            port == 5432
+            options == { "pool" == 5, "ssl" == False }
+
+            <cache>
+            ttl == 60
+            "#,
+        )
+        .unwrap();
+
+        config.apply_defaults(&defaults);
+        assert_eq!(config.get_string("database", "host"), Some("db.prod"));
+        assert_eq!(config.get_i64("database", "port"), Some(5432));
+        assert_eq!(config.get_i64("cache", "ttl"), Some(60));
+        let options = config.get_value("database", "options").unwrap();
+        assert_eq!(
+            options.as_object().unwrap().get("ssl"),
+            Some(&Value::Boolean(true))
+        );
+        assert_eq!(
+            options.as_object().unwrap().get("pool"),
+            Some(&Value::Integer(5))
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let config = ConfigBuilder::new()
+            .section("database")
+            .set("pool_size", 10)
+            .set("host", "localhost")
+            .section("database/advanced")
+            .set("ratio", 0.5)
+            .set("ssl", true)
+            .set("tags", vec![Value::from("a"), Value::from(2)])
+            .section("database")
+            .set("pool_size", 20)
+            .section("empty")
+            .build();
+
+        assert_eq!(config.get_i64("database", "pool_size"), Some(20));
+        assert_eq!(config.get_string("database", "host"), Some("localhost"));
+        assert_eq!(config.get_f64("database/advanced", "ratio"), Some(0.5));
+        assert_eq!(config.get_bool("database/advanced", "ssl"), Some(true));
+        assert_eq!(
+            config.get_value("database/advanced", "tags"),
+            Some(&Value::Array(vec![
+                Value::String("a".to_string()),
+                Value::Integer(2),
+            ]))
+        );
+        assert!(config.sections["empty"].is_empty());
+        assert_eq!(
+            config.to_string(),
+            "<database>\npool_size == 20\nhost == \"localhost\"\n\n\
+             <database<advanced>>\nratio == 0.5\nssl == True\ntags == [\"a\", 2]\n\n\
+             <empty>\n"
+        );
+    }
+
+    #[test]
+    fn test_builder_without_sections() {
+        assert_eq!(ConfigBuilder::new().build(), ThethaCoreConfig::new());
+        let config = ConfigBuilder::new().section("a").section("b").build();
+        assert_eq!(config.sorted_section_names(), ["a", "b"]);
+        assert!(config.is_empty());
+    }
+
+    #[test]
+    fn test_normalize_indexed_objects() {
+        let input = r#"
+        <legacy>
+        tags == { 0 == "a", 1 == "b" }
+        shuffled == { 2 == "c", 0 == "a", 1 == "b" }
+        nested == [{ 0 == { 0 == 1 } }, { "name" == { 0 == True } }]
+        gap == { 0 == "a", 2 == "c" }
+        padded == { 00 == "a" }
+        mixed == { 0 == "a", "name" == "b" }
+        empty == {}
+        "#;
+        let mut config = ThethaCoreConfig::parse(input).unwrap();
+        config.normalize_indexed_objects();
+        let value = |key| config.get_value("legacy", key).unwrap().to_string();
+
+        assert_eq!(
+            config.get_string_array("legacy", "tags"),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(value("shuffled"), r#"["a", "b", "c"]"#);
+        assert_eq!(value("nested"), r#"[[[1]], { "name" == [True] }]"#);
+        assert_eq!(value("gap"), r#"{ "0" == "a", "2" == "c" }"#);
+        assert_eq!(value("padded"), r#"{ "00" == "a" }"#);
+        assert_eq!(value("mixed"), r#"{ "0" == "a", "name" == "b" }"#);
+        assert_eq!(value("empty"), "{}");
+    }
+
+    #[test]
+    fn test_into_sorted() {
+        let input = r#"
+        <zeta>
+        b == 1
+        a == { "y" == 1, "x" == 2 }
+
+        <alpha<beta>>
+        port == 2
+        host == "h"
+
+        <alpha>
+        c == 3
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let sorted = config.clone().into_sorted();
+        assert_eq!(
+            sorted.keys().collect::<Vec<_>>(),
+            ["alpha", "alpha/beta", "zeta"]
+        );
+        assert_eq!(
+            sorted["alpha/beta"].keys().collect::<Vec<_>>(),
+            ["host", "port"]
+        );
+        assert_eq!(sorted["zeta"].keys().collect::<Vec<_>>(), ["a", "b"]);
+        let fields: Vec<&String> = sorted["zeta"]["a"].as_object().unwrap().keys().collect();
+        assert_eq!(fields, ["y", "x"]);
+        assert_eq!(sorted["zeta"]["b"], Value::Integer(1));
+        assert_eq!(
+            sorted.values().map(BTreeMap::len).sum::<usize>(),
+            config.len()
+        );
+    }
+
+    #[test]
+    fn test_section_entry() {
+        let mut config = ThethaCoreConfig::parse("<general>\nname == \"app\"").unwrap();
+        config
+            .section_entry("database/advanced")
+            .insert("pool_size".to_string(), Value::Integer(10));
+        config
+            .section_entry("general")
+            .insert("debug".to_string(), Value::Boolean(true));
+        assert_eq!(
+            config.sections.keys().collect::<Vec<_>>(),
+            ["general", "database/advanced"]
+        );
+        assert_eq!(config.get_i64("database/advanced", "pool_size"), Some(10));
+        assert_eq!(config.get_string("general", "name"), Some("app"));
+        assert_eq!(config.get_bool("general", "debug"), Some(true));
+
+        assert!(config.section_entry("empty").is_empty());
+        assert!(config.contains_section("empty"));
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = ThethaCoreConfig::parse(
+            r#"
+        <server>
+        host == "localhost"
+        port == 8080
+        debug == True
+        tls == { "enabled" == False, "cert" == "a.pem" }
+        "#,
+        )
+        .unwrap();
+        let new = ThethaCoreConfig::parse(
+            r#"
+        <server>
+        host == "localhost"
+        port == 9090
+        tls == { "enabled" == True, "cert" == "a.pem" }
+
+        <server<limits>>
+        max_body == 1MB
+        "#,
+        )
+        .unwrap();
+
+        let changes = old.diff(&new);
+        assert_eq!(
+            changes,
+            [
+                Change::Changed {
+                    path: "server/port".to_string(),
+                    old: Value::Integer(8080),
+                    new: Value::Integer(9090),
+                },
+                Change::Removed {
+                    path: "server/debug".to_string(),
+                    value: Value::Boolean(true),
+                },
+                Change::Changed {
+                    path: "server/tls".to_string(),
+                    old: old.get_value("server", "tls").unwrap().clone(),
+                    new: new.get_value("server", "tls").unwrap().clone(),
+                },
+                Change::Added {
+                    path: "server/limits/max_body".to_string(),
+                    value: Value::Bytes(1_000_000),
+                },
+            ]
+        );
+        assert_eq!(changes[3].path(), "server/limits/max_body");
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn test_len() {
+        let input = r#"
+        <general>
+        app_name == "TestApp"
+        tags == ["a", "b", "c"]
+
+        <empty>
+
+        <database<advanced>>
+        options == { "pool" == 10, "timeout" == 30s }
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.len(), 3);
+        assert!(!config.is_empty());
+
+        let config = ThethaCoreConfig::parse("<empty>\n<other>").unwrap();
+        assert_eq!(config.len(), 0);
+        assert!(config.is_empty());
+        assert!(ThethaCoreConfig::new().is_empty());
+    }
+
+    #[test]
+    fn test_iter() {
+        let input = r#"
+        <general>
+        app_name == "TestApp"
+        debug == False
+
+        <database<advanced>>
+        pool_size == 10
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let entries: Vec<(&str, &str, &Value)> = config.iter().collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("general", "app_name", &Value::String("TestApp".to_string())),
+                ("general", "debug", &Value::Boolean(false)),
+                ("database/advanced", "pool_size", &Value::Integer(10)),
+            ]
+        );
+
+        let strings: Vec<&Value> = config
+            .iter()
+            .filter(|(_, _, v)| matches!(v, Value::String(_)))
+            .map(|(_, _, v)| v)
+            .collect();
+        assert_eq!(strings.len(), 1);
+    }
+
+    #[test]
+    fn test_keys_with_prefix_and_glob() {
+        let input = r#"
+        <flags>
+        feature_login == True
+        features == "all"
+        beta_ui == False
+        feature_search == False
+        feature_ == 1
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.keys_with_prefix("flags", "feature_"),
+            vec![
+                ("feature_login", &Value::Boolean(true)),
+                ("feature_search", &Value::Boolean(false)),
+                ("feature_", &Value::Integer(1)),
+            ]
+        );
+        assert_eq!(config.keys_with_prefix("flags", "feature").len(), 4);
+        assert!(config.keys_with_prefix("flags", "nope").is_empty());
+        assert!(config.keys_with_prefix("missing", "").is_empty());
+
+        let keys = |pattern: &str| -> Vec<&str> {
+            config
+                .keys_matching("flags", pattern)
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect()
+        };
+        assert_eq!(
+            keys("feature_*"),
+            ["feature_login", "feature_search", "feature_"]
+        );
+        assert_eq!(keys("*_*i*"), ["feature_login", "beta_ui"]);
+        assert_eq!(keys("feature?"), ["features", "feature_"]);
+        assert_eq!(keys("beta_ui"), ["beta_ui"]);
+        assert_eq!(keys("*").len(), 5);
+    }
+}
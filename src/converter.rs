@@ -0,0 +1,927 @@
+use crate::errors::{ConversionError, ParseError};
+use crate::parser::{ThethaCoreConfig, Value, DEFAULT_MAX_DEPTH};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::time::Duration;
+
+impl ThethaCoreConfig {
+    /// Render the configuration as a compact JSON object.
+    ///
+    /// Each section path becomes a top-level field holding an object of its keys.
+    /// Fields keep the order of the configuration.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        out.push('{');
+        for (i, (name, section)) in self.sections.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write_json_string(&mut out, name);
+            out.push(':');
+            write_json_object(&mut out, section);
+        }
+        out.push('}');
+        out
+    }
+
+    /// Read a configuration from a JSON object, the inverse of
+    /// [`to_json`](Self::to_json).
+    ///
+    /// Each top-level field becomes a section and must hold an object of that
+    /// section's keys. Numbers without a fraction or exponent become integers.
+    /// Arrays and objects in the keys may nest up to [`DEFAULT_MAX_DEPTH`]
+    /// levels, as when parsing.
+    pub fn from_json(json: &str) -> Result<Self, ParseError> {
+        let mut reader = JsonReader {
+            src: json,
+            pos: 0,
+            depth: 0,
+        };
+        let mut config = ThethaCoreConfig::new();
+        reader.skip_whitespace();
+        if !reader.src[reader.pos..].starts_with('{') {
+            return Err(reader.error("Expected a JSON object of sections"));
+        }
+        reader.fields(|reader, name| {
+            reader.skip_whitespace();
+            if !reader.src[reader.pos..].starts_with('{') {
+                return Err(reader.error("Expected a JSON object of keys for section"));
+            }
+            let keys = reader.entries()?;
+            config.sections.insert(name, keys);
+            Ok(())
+        })?;
+        reader.skip_whitespace();
+        if reader.pos < json.len() {
+            return Err(reader.error("Unexpected characters after JSON value"));
+        }
+        Ok(config)
+    }
+
+    /// Flatten the configuration into a single map of scalar values.
+    ///
+    /// Keys are the section path and key joined by `.`, e.g.
+    /// `database/advanced.pool_size`. Object fields and array elements are
+    /// expanded into further segments, as in `options.pool` or `items.0`, so
+    /// empty arrays and objects don't appear at all. Entries keep the order of
+    /// the configuration.
+    pub fn flatten(&self) -> IndexMap<String, Value> {
+        let mut flat = IndexMap::new();
+        for (section, key, value) in self.iter() {
+            flatten_value(&mut flat, format!("{}.{}", section, key), value);
+        }
+        flat
+    }
+}
+
+fn flatten_value(flat: &mut IndexMap<String, Value>, prefix: String, value: &Value) {
+    match value {
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                flatten_value(flat, format!("{}.{}", prefix, i), item);
+            }
+        }
+        Value::Object(object) => {
+            for (key, item) in object {
+                flatten_value(flat, format!("{}.{}", prefix, key), item);
+            }
+        }
+        scalar => {
+            flat.insert(prefix, scalar.clone());
+        }
+    }
+}
+
+/// Implements `TryFrom<Value>` and `TryFrom<&Value>` for a target type by
+/// matching a single variant.
+macro_rules! impl_try_from {
+    ($owned:ty, $borrowed:ty, $variant:ident, $name:literal, $to_borrowed:expr) => {
+        impl TryFrom<Value> for $owned {
+            type Error = ConversionError;
+
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                match value {
+                    Value::$variant(inner) => Ok(inner),
+                    other => Err(ConversionError {
+                        expected: $name,
+                        found: other.type_name(),
+                    }),
+                }
+            }
+        }
+
+        impl<'a> TryFrom<&'a Value> for $borrowed {
+            type Error = ConversionError;
+
+            fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+                match value {
+                    Value::$variant(inner) => Ok($to_borrowed(inner)),
+                    other => Err(ConversionError {
+                        expected: $name,
+                        found: other.type_name(),
+                    }),
+                }
+            }
+        }
+    };
+}
+
+impl_try_from!(String, &'a str, String, "string", |s: &'a String| s
+    .as_str());
+impl_try_from!(i64, i64, Integer, "integer", |n: &i64| *n);
+impl_try_from!(f64, f64, Float, "float", |n: &f64| *n);
+impl_try_from!(bool, bool, Boolean, "boolean", |b: &bool| *b);
+impl_try_from!(Duration, Duration, Duration, "duration", |d: &Duration| *d);
+impl_try_from!(Vec<Value>, &'a [Value], Array, "array", |v: &'a Vec<
+    Value,
+>| v.as_slice());
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Integer(n)
+    }
+}
+
+/// Lets untyped integer literals, which default to `i32`, be used directly.
+impl From<i32> for Value {
+    fn from(n: i32) -> Self {
+        Value::Integer(n.into())
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Float(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Boolean(b)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(items: Vec<Value>) -> Self {
+        Value::Array(items)
+    }
+}
+
+impl From<IndexMap<String, Value>> for Value {
+    fn from(object: IndexMap<String, Value>) -> Self {
+        Value::Object(object)
+    }
+}
+
+/// The fields keep the `HashMap`'s iteration order, which is arbitrary; use an
+/// `IndexMap` to control it.
+impl From<HashMap<String, Value>> for Value {
+    fn from(object: HashMap<String, Value>) -> Self {
+        Value::Object(object.into_iter().collect())
+    }
+}
+
+fn write_json_value(out: &mut String, value: &Value) {
+    match value {
+        Value::String(s) => write_json_string(out, s),
+        Value::Integer(n) => out.push_str(&n.to_string()),
+        // JSON has no representation for NaN or infinity.
+        Value::Float(n) if !n.is_finite() => out.push_str("null"),
+        Value::Float(n) => out.push_str(&format!("{:?}", n)),
+        Value::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Null => out.push_str("null"),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_json_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(object) => write_json_object(out, object),
+        // Written in config syntax, e.g. "1h30m", since JSON has no duration type.
+        Value::Duration(_) => write_json_string(out, &value.to_string()),
+        Value::Bytes(n) => out.push_str(&n.to_string()),
+        #[cfg(feature = "chrono")]
+        Value::DateTime(_) => write_json_string(out, &value.to_string()),
+    }
+}
+
+fn write_json_object(out: &mut String, object: &IndexMap<String, Value>) {
+    out.push('{');
+    for (i, (key, value)) in object.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_json_string(out, key);
+        out.push(':');
+        write_json_value(out, value);
+    }
+    out.push('}');
+}
+
+fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// A minimal JSON reader for [`ThethaCoreConfig::from_json`].
+struct JsonReader<'a> {
+    src: &'a str,
+    pos: usize,
+    /// How many arrays and objects enclose the current position.
+    depth: usize,
+}
+
+impl JsonReader<'_> {
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.skip_whitespace();
+        let rest = &self.src[self.pos..];
+        match rest.chars().next() {
+            Some('{') => self.nested(|reader| reader.entries().map(Value::Object)),
+            Some('[') => self.nested(Self::array),
+            Some('"') => self.string().map(Value::String),
+            Some('-' | '0'..='9') => self.number(),
+            _ => {
+                for (word, value) in [
+                    ("true", Value::Boolean(true)),
+                    ("false", Value::Boolean(false)),
+                    ("null", Value::Null),
+                ] {
+                    if rest.starts_with(word) {
+                        self.pos += word.len();
+                        return Ok(value);
+                    }
+                }
+                Err(self.error("Unexpected JSON token"))
+            }
+        }
+    }
+
+    /// Read a container with `read`, one level deeper.
+    fn nested(
+        &mut self,
+        read: fn(&mut Self) -> Result<Value, ParseError>,
+    ) -> Result<Value, ParseError> {
+        if self.depth == DEFAULT_MAX_DEPTH {
+            let (line, column) = self.location(self.pos);
+            return Err(ParseError::NestingTooDeep {
+                line,
+                column,
+                limit: DEFAULT_MAX_DEPTH,
+            });
+        }
+        self.depth += 1;
+        let value = read(self);
+        self.depth -= 1;
+        value
+    }
+
+    /// Read the fields of the object starting at the current `{`.
+    fn entries(&mut self) -> Result<IndexMap<String, Value>, ParseError> {
+        let mut object = IndexMap::new();
+        self.fields(|reader, key| {
+            let value = reader.value()?;
+            object.insert(key, value);
+            Ok(())
+        })?;
+        Ok(object)
+    }
+
+    /// Read the object starting at the current `{`, calling `field` with each
+    /// key to read the value that follows it.
+    fn fields(
+        &mut self,
+        mut field: impl FnMut(&mut Self, String) -> Result<(), ParseError>,
+    ) -> Result<(), ParseError> {
+        self.pos += 1;
+        self.skip_whitespace();
+        if self.eat('}') {
+            return Ok(());
+        }
+        loop {
+            self.skip_whitespace();
+            if !self.src[self.pos..].starts_with('"') {
+                return Err(self.error("Expected a JSON string key"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            if !self.eat(':') {
+                return Err(self.error("Expected ':' after JSON key"));
+            }
+            field(self, key)?;
+            self.skip_whitespace();
+            if self.eat('}') {
+                return Ok(());
+            }
+            if !self.eat(',') {
+                return Err(self.error("Expected ',' or '}' in JSON object"));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, ParseError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.eat(']') {
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            if !self.eat(',') {
+                return Err(self.error("Expected ',' or ']' in JSON array"));
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut out = String::new();
+        let mut chars = self.src[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(out);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let code = match hex_unit(&mut chars) {
+                                Some(high @ 0xD800..=0xDBFF) => {
+                                    let low = chars.next().zip(chars.next());
+                                    match (low, hex_unit(&mut chars)) {
+                                        (
+                                            Some(((_, '\\'), (_, 'u'))),
+                                            Some(low @ 0xDC00..=0xDFFF),
+                                        ) => {
+                                            Some(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+                                        }
+                                        _ => None,
+                                    }
+                                }
+                                code => code,
+                            };
+                            match code.and_then(char::from_u32) {
+                                Some(c) => c,
+                                None => {
+                                    self.pos += i;
+                                    return Err(self.error("Invalid JSON unicode escape"));
+                                }
+                            }
+                        }
+                        _ => {
+                            self.pos += i;
+                            return Err(self.error("Invalid JSON escape"));
+                        }
+                    };
+                    out.push(escaped);
+                }
+                c => out.push(c),
+            }
+        }
+        Err(self.error_at(start, "Unterminated JSON string"))
+    }
+
+    /// Read a number, which must follow the JSON grammar
+    /// `-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?`.
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        let bytes = self.src.as_bytes();
+        let mut end = start + usize::from(bytes[start] == b'-');
+        let int_start = end;
+        let mut valid =
+            skip_digits(bytes, &mut end) && !(bytes[int_start] == b'0' && end - int_start > 1);
+        let int_end = end;
+        if bytes.get(end) == Some(&b'.') {
+            end += 1;
+            valid &= skip_digits(bytes, &mut end);
+        }
+        if matches!(bytes.get(end), Some(b'e' | b'E')) {
+            end += 1;
+            if matches!(bytes.get(end), Some(b'+' | b'-')) {
+                end += 1;
+            }
+            valid &= skip_digits(bytes, &mut end);
+        }
+        // Trailing number characters, as in `1.2.3` or `1e5e`, make it invalid
+        // rather than ending it.
+        valid &= !matches!(
+            bytes.get(end),
+            Some(b'0'..=b'9' | b'+' | b'-' | b'.' | b'e' | b'E')
+        );
+        let text = &self.src[start..end];
+        self.pos = end;
+        let value = if !valid {
+            None
+        } else if end > int_end {
+            text.parse().ok().map(Value::Float)
+        } else {
+            text.parse().ok().map(Value::Integer)
+        };
+        value.ok_or_else(|| self.error_at(start, "Invalid JSON number"))
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.src[self.pos..].starts_with(c);
+        if found {
+            self.pos += c.len_utf8();
+        }
+        found
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.src[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn error(&self, reason: &str) -> ParseError {
+        self.error_at(self.pos, reason)
+    }
+
+    /// The line and column of byte `offset`.
+    fn location(&self, offset: usize) -> (usize, usize) {
+        let before = &self.src[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+
+    /// An error pointing at byte `offset`, quoting the rest of its line.
+    fn error_at(&self, offset: usize, reason: &str) -> ParseError {
+        let (line, column) = self.location(offset);
+        let rest = &self.src[offset..];
+        ParseError::InvalidValue {
+            line,
+            column,
+            text: rest[..rest.find('\n').unwrap_or(rest.len())]
+                .trim_end()
+                .to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// Move `end` past the ASCII digits starting there, returning whether there
+/// were any.
+fn skip_digits(bytes: &[u8], end: &mut usize) -> bool {
+    let start = *end;
+    while bytes.get(*end).is_some_and(u8::is_ascii_digit) {
+        *end += 1;
+    }
+    *end > start
+}
+
+/// Read the four hex digits of a `\u` escape.
+fn hex_unit(chars: &mut std::str::CharIndices) -> Option<u32> {
+    let hex: String = chars.take(4).map(|(_, c)| c).collect();
+    u32::from_str_radix(&hex, 16)
+        .ok()
+        .filter(|_| hex.len() == 4)
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use crate::parser::Value;
+    use indexmap::IndexMap;
+    use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+    use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+    use std::fmt;
+
+    impl Serialize for Value {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                Value::String(s) => serializer.serialize_str(s),
+                Value::Integer(n) => serializer.serialize_i64(*n),
+                Value::Float(n) => serializer.serialize_f64(*n),
+                Value::Boolean(b) => serializer.serialize_bool(*b),
+                Value::Null => serializer.serialize_unit(),
+                Value::Array(items) => {
+                    let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                    for item in items {
+                        seq.serialize_element(item)?;
+                    }
+                    seq.end()
+                }
+                Value::Object(object) => {
+                    let mut map = serializer.serialize_map(Some(object.len()))?;
+                    for (k, v) in object {
+                        map.serialize_entry(k, v)?;
+                    }
+                    map.end()
+                }
+                Value::Duration(_) => serializer.serialize_str(&self.to_string()),
+                Value::Bytes(n) => serializer.serialize_u64(*n),
+                #[cfg(feature = "chrono")]
+                Value::DateTime(_) => serializer.serialize_str(&self.to_string()),
+            }
+        }
+    }
+
+    struct ValueVisitor;
+
+    impl<'de> Visitor<'de> for ValueVisitor {
+        type Value = Value;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a ThethaCore value")
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+            Ok(Value::Boolean(v))
+        }
+
+        fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+            Ok(Value::Integer(v))
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+            i64::try_from(v)
+                .map(Value::Integer)
+                .map_err(|_| E::custom(format!("integer {} is too large for i64", v)))
+        }
+
+        fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+            Ok(Value::Float(v))
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+            Ok(Value::String(v.to_string()))
+        }
+
+        fn visit_string<E>(self, v: String) -> Result<Value, E> {
+            Ok(Value::String(v))
+        }
+
+        fn visit_unit<E>(self) -> Result<Value, E> {
+            Ok(Value::Null)
+        }
+
+        fn visit_none<E>(self) -> Result<Value, E> {
+            Ok(Value::Null)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+            Deserialize::deserialize(deserializer)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+            let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(item) = seq.next_element()? {
+                items.push(item);
+            }
+            Ok(Value::Array(items))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+            let mut object = IndexMap::with_capacity(map.size_hint().unwrap_or(0));
+            while let Some((k, v)) = map.next_entry()? {
+                object.insert(k, v);
+            }
+            Ok(Value::Object(object))
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Value {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+            deserializer.deserialize_any(ValueVisitor)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::parser::ThethaCoreConfig;
+
+        #[test]
+        fn test_serde_serialize_value() {
+            let input = r#"
+            <data>
+            items == [1, 2.5, "three", True, Null]
+            obj == { "a" == [1] }
+            "#;
+            let config = ThethaCoreConfig::parse(input).unwrap();
+            let data = config.sections.get("data").unwrap();
+            assert_eq!(
+                serde_json::to_string(data.get("items").unwrap()).unwrap(),
+                r#"[1,2.5,"three",true,null]"#
+            );
+            assert_eq!(
+                serde_json::to_string(data.get("obj").unwrap()).unwrap(),
+                r#"{"a":[1]}"#
+            );
+        }
+
+        #[test]
+        fn test_serde_deserialize_value() {
+            let value: Value =
+                serde_json::from_str(r#"{"a": [1, 2.5, "x", false, null]}"#).unwrap();
+            if let Value::Object(obj) = value {
+                assert_eq!(
+                    obj.get("a"),
+                    Some(&Value::Array(vec![
+                        Value::Integer(1),
+                        Value::Float(2.5),
+                        Value::String("x".to_string()),
+                        Value::Boolean(false),
+                        Value::Null,
+                    ]))
+                );
+            } else {
+                panic!("Expected an object");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_json_round_trip() {
+        let json = r#"{"general":{"name":"App \"x\"\n","version":1.5,"debug":true},"database/advanced":{"ports":[1,-2,3e2],"options":{"pool":null,"tags":[]}},"empty":{}}"#;
+        let config = ThethaCoreConfig::from_json(json).unwrap();
+        assert_eq!(
+            config.get_value("database/advanced", "ports"),
+            Some(&Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(-2),
+                Value::Float(300.0),
+            ]))
+        );
+        assert_eq!(config.get_string("general", "name"), Some("App \"x\"\n"));
+        assert_eq!(config.to_json(), json.replace("3e2", "300.0"));
+
+        let pretty = "{\n  \"s\": { \"k\": \"\\u00e9\\ud83d\\ude00\" }\n}\n";
+        let config = ThethaCoreConfig::from_json(pretty).unwrap();
+        assert_eq!(config.get_string("s", "k"), Some("é😀"));
+    }
+
+    #[test]
+    fn test_from_json_errors() {
+        let message = |json: &str| ThethaCoreConfig::from_json(json).unwrap_err().to_string();
+        assert_eq!(
+            message("[1, 2]"),
+            "❌ Syntax error on line 1, col 1: Expected a JSON object of sections '[1, 2]'"
+        );
+        assert_eq!(
+            message("{\n  \"a\": {},\n  \"b\": 5\n}"),
+            "❌ Syntax error on line 3, col 8: Expected a JSON object of keys for section '5'"
+        );
+        assert_eq!(
+            message(r#"{"a": {"k": tru}}"#),
+            "❌ Syntax error on line 1, col 13: Unexpected JSON token 'tru}}'"
+        );
+        assert!(message(r#"{"a": {"k": "open}}"#).contains("Unterminated JSON string"));
+        assert!(message(r#"{"a": {}} x"#).contains("Unexpected characters after JSON value"));
+        assert!(message("").contains("Expected a JSON object of sections"));
+
+        for number in ["01", "-01", "1.", "-", "1e", "1e+", "1.2.3", "1e5e", "--1"] {
+            let json = format!(r#"{{"a": {{"k": [{}]}}}}"#, number);
+            assert_eq!(
+                message(&json),
+                format!(
+                    "❌ Syntax error on line 1, col 14: Invalid JSON number '{}]}}}}'",
+                    number
+                )
+            );
+        }
+        for number in ["+1", ".5"] {
+            let json = format!(r#"{{"a": {{"k": [{}]}}}}"#, number);
+            assert!(message(&json).contains("Unexpected JSON token"), "{number}");
+        }
+        let numbers =
+            ThethaCoreConfig::from_json(r#"{"a": {"k": [0, -0, 10, 0.5, -1.25e-3, 2E+2, 1e0]}}"#)
+                .unwrap();
+        assert_eq!(
+            numbers.get_value("a", "k"),
+            Some(&Value::Array(vec![
+                Value::Integer(0),
+                Value::Integer(0),
+                Value::Integer(10),
+                Value::Float(0.5),
+                Value::Float(-1.25e-3),
+                Value::Float(200.0),
+                Value::Float(1.0),
+            ]))
+        );
+
+        let nested = |depth: usize| {
+            let json = format!(
+                r#"{{"a": {{"k": {}{}}}}}"#,
+                "[".repeat(depth),
+                "]".repeat(depth)
+            );
+            ThethaCoreConfig::from_json(&json)
+        };
+        assert!(nested(DEFAULT_MAX_DEPTH).is_ok());
+        assert!(matches!(
+            nested(DEFAULT_MAX_DEPTH + 1),
+            Err(ParseError::NestingTooDeep {
+                line: 1,
+                column: 141,
+                limit: DEFAULT_MAX_DEPTH,
+            })
+        ));
+        assert!(matches!(
+            nested(200_000),
+            Err(ParseError::NestingTooDeep { .. })
+        ));
+    }
+
+    #[test]
+    fn test_to_json_array() {
+        let input = r#"
+        <data>
+        items == [1, 2, 3]
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.to_json(), r#"{"data":{"items":[1,2,3]}}"#);
+    }
+
+    #[test]
+    fn test_to_json_nested() {
+        let input = r#"
+        <general>
+        name == "say \"hi\""
+        ratio == 0.5
+        enabled == True
+        missing == Null
+
+        <database<advanced>>
+        options == { "pool" == [1, { "x" == False }] }
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.to_json(),
+            r#"{"general":{"name":"say \"hi\"","ratio":0.5,"enabled":true,"missing":null},"#
+                .to_string()
+                + r#""database/advanced":{"options":{"pool":[1,{"x":false}]}}}"#
+        );
+    }
+
+    #[test]
+    fn test_to_json_duration() {
+        let config = ThethaCoreConfig::parse("<t>\ntimeout == 1h30m").unwrap();
+        assert_eq!(config.to_json(), r#"{"t":{"timeout":"1h30m"}}"#);
+        assert_eq!(
+            Duration::try_from(config.get_value("t", "timeout").unwrap()),
+            Ok(Duration::from_secs(5400))
+        );
+    }
+
+    #[test]
+    fn test_to_json_bytes() {
+        let config = ThethaCoreConfig::parse("<l>\nbuffer == 512KiB").unwrap();
+        assert_eq!(config.to_json(), r#"{"l":{"buffer":524288}}"#);
+    }
+
+    #[test]
+    fn test_flatten() {
+        let input = r#"
+        <general>
+        name == "app"
+
+        <database<advanced>>
+        pool_size == 10
+        options == { "ssl" == True, "hosts" == ["a", "b"], "empty" == {} }
+        items == []
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let flat = config.flatten();
+        let keys: Vec<&str> = flat.keys().map(String::as_str).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "general.name",
+                "database/advanced.pool_size",
+                "database/advanced.options.ssl",
+                "database/advanced.options.hosts.0",
+                "database/advanced.options.hosts.1",
+            ]
+        );
+        assert_eq!(
+            flat.get("database/advanced.options.hosts.1"),
+            Some(&Value::String("b".to_string()))
+        );
+        assert_eq!(
+            flat.get("database/advanced.pool_size"),
+            Some(&Value::Integer(10))
+        );
+    }
+
+    #[test]
+    fn test_from_primitives() {
+        assert_eq!(Value::from("db"), Value::String("db".to_string()));
+        assert_eq!(
+            Value::from("db".to_string()),
+            Value::String("db".to_string())
+        );
+        assert_eq!(Value::from(42), Value::Integer(42));
+        assert_eq!(Value::from(42i64), Value::Integer(42));
+        assert_eq!(Value::from(0.5), Value::Float(0.5));
+        assert_eq!(Value::from(true), Value::Boolean(true));
+        assert_eq!(
+            Value::from(vec![Value::from(1), Value::from("x")]),
+            Value::Array(vec![Value::Integer(1), Value::String("x".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_from_maps() {
+        let map = HashMap::from([("pool".to_string(), Value::from(5))]);
+        assert_eq!(
+            Value::from(map),
+            Value::Object(IndexMap::from([("pool".to_string(), Value::Integer(5))]))
+        );
+
+        let ordered = IndexMap::from([
+            ("b".to_string(), Value::from(1)),
+            ("a".to_string(), Value::from(2)),
+        ]);
+        assert_eq!(Value::from(ordered.clone()), Value::Object(ordered));
+    }
+
+    #[test]
+    fn test_try_from_owned() {
+        let port: i64 = Value::Integer(5432).try_into().unwrap();
+        assert_eq!(port, 5432);
+        let name: String = Value::String("db".to_string()).try_into().unwrap();
+        assert_eq!(name, "db");
+        assert_eq!(f64::try_from(Value::Float(0.5)), Ok(0.5));
+        assert_eq!(bool::try_from(Value::Boolean(true)), Ok(true));
+        assert_eq!(
+            Vec::<Value>::try_from(Value::Array(vec![Value::Null])),
+            Ok(vec![Value::Null])
+        );
+    }
+
+    #[test]
+    fn test_try_from_borrowed() {
+        let value = Value::String("db".to_string());
+        let name: &str = (&value).try_into().unwrap();
+        assert_eq!(name, "db");
+        let items = Value::Array(vec![Value::Integer(1)]);
+        assert_eq!(<&[Value]>::try_from(&items), Ok(&[Value::Integer(1)][..]));
+        assert_eq!(i64::try_from(&Value::Integer(2)), Ok(2));
+    }
+
+    #[test]
+    fn test_try_from_mismatch() {
+        let err = i64::try_from(Value::String("5432".to_string())).unwrap_err();
+        assert_eq!(
+            err,
+            ConversionError {
+                expected: "integer",
+                found: "string"
+            }
+        );
+        assert_eq!(err.to_string(), "❌ Error: Expected integer, found string");
+        assert_eq!(
+            <&str>::try_from(&Value::Null).unwrap_err().to_string(),
+            "❌ Error: Expected string, found null"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Deserializing configurations straight into Rust types with `serde`.
+//!
+//! The configuration is treated as a map from section paths to maps of keys,
+//! so sections become fields of the target struct and their keys become fields
+//! of nested structs. Nested sections keep their full path as the field name,
+//! e.g. `#[serde(rename = "database/advanced")]`.
+
+use crate::errors::DeserializeError;
+use crate::parser::{ThethaCoreConfig, Value};
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{DeserializeOwned, Deserializer, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+/// Parse `input` and deserialize it into `T`.
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T, DeserializeError> {
+    from_config(&ThethaCoreConfig::parse(input)?)
+}
+
+/// Deserialize an already parsed configuration into `T`.
+pub fn from_config<T: DeserializeOwned>(config: &ThethaCoreConfig) -> Result<T, DeserializeError> {
+    let sections = config
+        .sections
+        .iter()
+        .map(|(name, section)| (name.clone(), Value::Object(section.clone())))
+        .collect();
+    T::deserialize(Value::Object(sections))
+}
+
+impl<'de> IntoDeserializer<'de, DeserializeError> for Value {
+    type Deserializer = Value;
+
+    fn into_deserializer(self) -> Value {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match self {
+            Value::String(s) => visitor.visit_string(s),
+            Value::Integer(n) => visitor.visit_i64(n),
+            Value::Float(n) => visitor.visit_f64(n),
+            Value::Boolean(b) => visitor.visit_bool(b),
+            Value::Null => visitor.visit_unit(),
+            Value::Array(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Object(object) => {
+                let mut map = MapDeserializer::new(object.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            // Handed over in config syntax, matching how `Value` serializes.
+            Value::Duration(_) => visitor.visit_string(self.to_string()),
+            Value::Bytes(n) => visitor.visit_u64(n),
+            #[cfg(feature = "chrono")]
+            Value::DateTime(_) => visitor.visit_string(self.to_string()),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeserializeError> {
+        match self {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Only unit variants are supported, written as strings.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeserializeError> {
+        match self {
+            Value::String(s) => {
+                visitor.visit_enum(IntoDeserializer::<DeserializeError>::into_deserializer(s))
+            }
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Server {
+        host: String,
+        port: u16,
+        debug: bool,
+        ratio: f64,
+        name: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Level {
+        Low,
+        High,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Limits {
+        tags: Vec<String>,
+        level: Level,
+        size: u64,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct App {
+        server: Server,
+        #[serde(rename = "server/limits")]
+        limits: Limits,
+    }
+
+    #[test]
+    fn test_from_str_sections_and_scalars() {
+        let input = r#"
+        <server>
+        host == "localhost"
+        port == 8080
+        debug == True
+        ratio == 2
+        name == Null
+
+        <server<limits>>
+        tags == ["a", "b"]
+        level == "High"
+        size == 1KiB
+        "#;
+        let app: App = from_str(input).unwrap();
+        assert_eq!(
+            app,
+            App {
+                server: Server {
+                    host: "localhost".to_string(),
+                    port: 8080,
+                    debug: true,
+                    ratio: 2.0,
+                    name: None,
+                },
+                limits: Limits {
+                    tags: vec!["a".to_string(), "b".to_string()],
+                    level: Level::High,
+                    size: 1024,
+                },
+            }
+        );
+        assert_ne!(Level::Low, app.limits.level);
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Only {
+            server: Server,
+        }
+
+        let err = from_str::<Only>("<server>\nhost == \"h\"\n").unwrap_err();
+        assert_eq!(err.to_string(), "❌ Error: missing field `port`");
+
+        let err = from_str::<Only>(
+            "<server>\nhost == \"h\"\nport == 70000\ndebug == False\nratio == 1.0\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("70000"));
+
+        let err = from_str::<Only>("port = 1").unwrap_err();
+        assert!(matches!(err, DeserializeError::Parse(_)));
+    }
+}
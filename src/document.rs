@@ -0,0 +1,235 @@
+//! Parsing that keeps comments, for tools that edit a configuration and
+//! write it back.
+
+use crate::errors::ParseError;
+use crate::formatter::write_section;
+use crate::parser::{ParseContext, ParserOptions, ThethaCoreConfig};
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+
+/// Comment lines collected while parsing, keyed by what they come before.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Comments {
+    /// Comments not yet followed by a section header or key.
+    pub(crate) pending: Vec<String>,
+    sections: HashMap<String, Vec<String>>,
+    keys: HashMap<String, HashMap<String, Vec<String>>>,
+    /// Comments after the last section header or key.
+    pub(crate) trailing: Vec<String>,
+}
+
+impl Comments {
+    /// The comment lines before the headers of `section`.
+    pub(crate) fn section(&self, section: &str) -> &[String] {
+        self.sections.get(section).map_or(&[], Vec::as_slice)
+    }
+
+    /// The comment lines before `key` in `section`.
+    pub(crate) fn key(&self, section: &str, key: &str) -> &[String] {
+        self.keys
+            .get(section)
+            .and_then(|keys| keys.get(key))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Attach the pending comments to the header of `section`.
+    pub(crate) fn attach_to_section(&mut self, section: &str) {
+        let pending = mem::take(&mut self.pending);
+        if !pending.is_empty() {
+            self.sections
+                .entry(section.to_string())
+                .or_default()
+                .extend(pending);
+        }
+    }
+
+    /// Attach the pending comments to `key` in `section`.
+    pub(crate) fn attach_to_key(&mut self, section: &str, key: &str) {
+        let pending = mem::take(&mut self.pending);
+        if !pending.is_empty() {
+            self.keys
+                .entry(section.to_string())
+                .or_default()
+                .entry(key.to_string())
+                .or_default()
+                .extend(pending);
+        }
+    }
+}
+
+/// A configuration together with its comments.
+///
+/// Each `#`, `//` or `;` comment line is kept with the section header or key
+/// that follows it; comments after the last key are kept at the end. Edit the
+/// values through [`config_mut`](Self::config_mut): `Display` then writes the
+/// configuration like [`ThethaCoreConfig`]'s, with every comment still in
+/// front of its section or key. Comments on removed sections and keys are
+/// dropped, and inline and `/* */` comments aren't kept.
+#[derive(Debug, Clone, Default)]
+pub struct Document {
+    config: ThethaCoreConfig,
+    comments: Comments,
+}
+
+impl Document {
+    /// Parse a configuration like [`ThethaCoreConfig::parse`], keeping its
+    /// comment lines.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut comments = Comments::default();
+        let config = ThethaCoreConfig::parse_inner(
+            input.lines().map(Ok),
+            &ParserOptions::default(),
+            &mut ParseContext {
+                comments: Some(&mut comments),
+                ..ParseContext::default()
+            },
+        )?;
+        Ok(Document { config, comments })
+    }
+
+    pub fn config(&self) -> &ThethaCoreConfig {
+        &self.config
+    }
+
+    pub fn config_mut(&mut self) -> &mut ThethaCoreConfig {
+        &mut self.config
+    }
+
+    /// The comment lines before the header of `section`, including those
+    /// before later headers reopening it.
+    pub fn section_comments(&self, section: &str) -> &[String] {
+        self.comments.section(section)
+    }
+
+    /// The comment lines before `key` in `section`.
+    pub fn key_comments(&self, section: &str, key: &str) -> &[String] {
+        self.comments.key(section, key)
+    }
+}
+
+/// Start a document without comments from an existing configuration.
+impl From<ThethaCoreConfig> for Document {
+    fn from(config: ThethaCoreConfig) -> Self {
+        Document {
+            config,
+            comments: Comments::default(),
+        }
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, section)) in self.config.sections.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write_section(f, name, section, Some(&self.comments))?;
+        }
+        if !self.comments.trailing.is_empty() {
+            if !self.config.sections.is_empty() {
+                writeln!(f)?;
+            }
+            for comment in &self.comments.trailing {
+                writeln!(f, "{}", comment)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Value;
+
+    const INPUT: &str = r#"
+    # Settings for the demo app.
+    // Keep this file in sync with the docs.
+    <general>
+    name == "demo"
+    ; Seconds between polls.
+    interval == 30
+
+    # Connection settings
+    <database<advanced>>
+    tags == [
+        # not kept, but harmless
+        "a",
+    ]
+    # How many connections to keep open.
+    pool_size == 10 # inline comments are dropped
+
+    # End of file.
+    "#;
+
+    #[test]
+    fn test_round_trip_preserves_comments() {
+        let doc = Document::parse(INPUT).unwrap();
+        assert_eq!(
+            doc.section_comments("general"),
+            [
+                "# Settings for the demo app.",
+                "// Keep this file in sync with the docs."
+            ]
+        );
+        assert_eq!(
+            doc.key_comments("general", "interval"),
+            ["; Seconds between polls."]
+        );
+        assert!(doc.key_comments("general", "name").is_empty());
+        assert!(doc.section_comments("missing").is_empty());
+
+        let expected = "\
+# Settings for the demo app.
+// Keep this file in sync with the docs.
+<general>
+name == \"demo\"
+; Seconds between polls.
+interval == 30
+
+# Connection settings
+<database<advanced>>
+tags == [\"a\"]
+# How many connections to keep open.
+pool_size == 10
+
+# End of file.
+";
+        assert_eq!(doc.to_string(), expected);
+
+        let reparsed = Document::parse(&doc.to_string()).unwrap();
+        assert_eq!(reparsed.to_string(), expected);
+        assert_eq!(reparsed.config(), doc.config());
+    }
+
+    #[test]
+    fn test_edit_keeps_comments() {
+        let mut doc = Document::parse(INPUT).unwrap();
+        let config = doc.config_mut();
+        config
+            .section_entry("database/advanced")
+            .insert("pool_size".to_string(), Value::Integer(20));
+        config.remove_key("general", "interval");
+        config
+            .section_entry("general")
+            .insert("debug".to_string(), Value::Boolean(true));
+
+        let output = doc.to_string();
+        assert!(output.contains("# How many connections to keep open.\npool_size == 20\n"));
+        assert!(output.contains("name == \"demo\"\ndebug == True\n"));
+        assert!(!output.contains("Seconds between polls"));
+        assert_eq!(ThethaCoreConfig::parse(&output).unwrap(), *doc.config());
+    }
+
+    #[test]
+    fn test_document_without_comments() {
+        let config = ThethaCoreConfig::parse("<a>\nx == 1").unwrap();
+        let doc = Document::from(config.clone());
+        assert_eq!(doc.to_string(), config.to_string());
+
+        let doc = Document::parse("# only a comment\n").unwrap();
+        assert_eq!(doc.to_string(), "# only a comment\n");
+        assert!(Document::parse("<a>\nx == @").is_err());
+    }
+}
@@ -0,0 +1,569 @@
+use crate::validator::ValueKind;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::ops::RangeInclusive;
+
+/// An error encountered while reading or parsing a ThethaCore configuration.
+///
+/// Line and column numbers are 1-based; columns count characters and point at
+/// the start of the offending token.
+#[derive(Debug)]
+pub enum ParseError {
+    /// A line that is neither a comment, a section header nor a key-value pair.
+    UnexpectedToken {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// A key followed by a single `=` instead of `==`.
+    SingleEquals {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// A section header with a name containing `>`, such as `<a>b>`.
+    InvalidSectionHeader {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// A section header missing its closing `>`, or its opening `<`.
+    UnterminatedSectionHeader {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// A section header with an empty or whitespace-only name, such as `<>`
+    /// or `<a<>>`.
+    EmptySectionName {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// A `</name>` close marker that doesn't name the open section; `open` is
+    /// `None` if no section was open.
+    MismatchedSectionClose {
+        line: usize,
+        column: usize,
+        found: String,
+        open: Option<String>,
+    },
+    /// A section left without a `</name>` close marker while parsing with
+    /// [`ParserOptions::strict_sections`](crate::ParserOptions::strict_sections);
+    /// the position is that of its header.
+    UnclosedSection {
+        line: usize,
+        column: usize,
+        section: String,
+    },
+    /// A section without keys, with
+    /// [`ParserOptions::require_non_empty_sections`](crate::ParserOptions::require_non_empty_sections) set.
+    EmptySection {
+        line: usize,
+        column: usize,
+        section: String,
+    },
+    /// A `/*` comment with no closing `*/`.
+    UnterminatedBlockComment { line: usize, column: usize },
+    /// A key-value pair that appears before any section header.
+    ValueOutsideSection {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// A value that could not be parsed; `reason` describes what went wrong.
+    InvalidValue {
+        line: usize,
+        column: usize,
+        text: String,
+        reason: String,
+    },
+    /// Arrays and objects nested more deeply than
+    /// [`ParserOptions::max_depth`](crate::ParserOptions::max_depth) allows.
+    NestingTooDeep {
+        line: usize,
+        column: usize,
+        limit: usize,
+    },
+    /// An array or object with more elements than
+    /// [`ParserOptions::max_elements`](crate::ParserOptions::max_elements)
+    /// allows; the position is that of its opening bracket.
+    TooManyElements {
+        line: usize,
+        column: usize,
+        limit: usize,
+    },
+    /// An integer literal that doesn't fit in an `i64`.
+    IntegerOverflow {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// A key defined twice in the same section while parsing strictly.
+    DuplicateKey {
+        line: usize,
+        column: usize,
+        key: String,
+        first_line: usize,
+    },
+    /// A `${VAR}` reference to an unset environment variable with no fallback.
+    UndefinedVariable {
+        line: usize,
+        column: usize,
+        name: String,
+    },
+    /// An `@include` directive that cannot be followed, e.g. because the file
+    /// was already included.
+    InvalidInclude {
+        line: usize,
+        column: usize,
+        path: String,
+        reason: String,
+    },
+    /// An error in the contents of a file read by
+    /// [`parse_from_file`](crate::ThethaCoreConfig::parse_from_file), including
+    /// files it includes; `path` names the file the error is in.
+    InFile {
+        path: String,
+        error: Box<ParseError>,
+    },
+    /// Input longer than
+    /// [`ParserOptions::max_input_len`](crate::ParserOptions::max_input_len)
+    /// allows; `line` is the line where the limit was passed.
+    InputTooLarge { line: usize, limit: usize },
+    /// Input with more lines than
+    /// [`ParserOptions::max_lines`](crate::ParserOptions::max_lines) allows.
+    TooManyLines { limit: usize },
+    /// Input given as bytes is not valid UTF-8; `offset` is the index of the
+    /// first invalid byte.
+    InvalidUtf8 { offset: usize },
+    /// A [`ParserOptions::separator`](crate::ParserOptions::separator) that
+    /// is empty, or holds whitespace or characters that can start or be part
+    /// of a key, a value or a comment.
+    InvalidSeparator { separator: String },
+    /// The input file or reader could not be read.
+    Io { path: String, source: io::Error },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_message(f, None)
+    }
+}
+
+impl ParseError {
+    /// The file the error occurred in, if it came from
+    /// [`parse_from_file`](crate::ThethaCoreConfig::parse_from_file).
+    pub fn source_path(&self) -> Option<&str> {
+        match self {
+            ParseError::InFile { path, .. } => Some(path),
+            ParseError::Io { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Write the message, naming `path` (if given) before the line number.
+    fn write_message(&self, f: &mut fmt::Formatter<'_>, path: Option<&str>) -> fmt::Result {
+        let at = |line: &usize, column: &usize| Location {
+            path,
+            line: *line,
+            column: *column,
+        };
+        match self {
+            ParseError::UnexpectedToken { line, column, text } => {
+                write!(f, "❌ Syntax error {}: '{}'", at(line, column), text)
+            }
+            ParseError::SingleEquals { line, column, text } => write!(
+                f,
+                "❌ Syntax error {}: Found '=' in '{}'; use '==' to assign a value",
+                at(line, column),
+                text
+            ),
+            ParseError::InvalidSectionHeader { line, column, text } => write!(
+                f,
+                "❌ Syntax error {}: Invalid section header '{}'",
+                at(line, column),
+                text
+            ),
+            ParseError::UnterminatedSectionHeader { line, column, text } => write!(
+                f,
+                "❌ Syntax error {}: Unterminated section header '{}'",
+                at(line, column),
+                text
+            ),
+            ParseError::EmptySectionName { line, column, text } => write!(
+                f,
+                "❌ Syntax error {}: Empty section name in header '{}'",
+                at(line, column),
+                text
+            ),
+            ParseError::MismatchedSectionClose {
+                line,
+                column,
+                found,
+                open: Some(open),
+            } => write!(
+                f,
+                "❌ Syntax error {}: Close marker '</{}>' doesn't match the open section '{}'",
+                at(line, column),
+                found,
+                open
+            ),
+            ParseError::MismatchedSectionClose {
+                line,
+                column,
+                found,
+                open: None,
+            } => write!(
+                f,
+                "❌ Syntax error {}: Close marker '</{}>' has no open section",
+                at(line, column),
+                found
+            ),
+            ParseError::UnclosedSection {
+                line,
+                column,
+                section,
+            } => write!(
+                f,
+                "❌ Error {}: Section '{}' is never closed",
+                at(line, column),
+                section
+            ),
+            ParseError::EmptySection {
+                line,
+                column,
+                section,
+            } => write!(
+                f,
+                "❌ Error {}: Section '{}' has no keys",
+                at(line, column),
+                section
+            ),
+            ParseError::UnterminatedBlockComment { line, column } => write!(
+                f,
+                "❌ Syntax error {}: Unterminated block comment",
+                at(line, column)
+            ),
+            ParseError::ValueOutsideSection { line, column, .. } => write!(
+                f,
+                "❌ Error {}: Key-value pair found outside of a section",
+                at(line, column)
+            ),
+            ParseError::InvalidValue {
+                line,
+                column,
+                text,
+                reason,
+            } => write!(
+                f,
+                "❌ Syntax error {}: {} '{}'",
+                at(line, column),
+                reason,
+                text
+            ),
+            ParseError::NestingTooDeep {
+                line,
+                column,
+                limit,
+            } => write!(
+                f,
+                "❌ Syntax error {}: Nesting too deep (the limit is {} levels)",
+                at(line, column),
+                limit
+            ),
+            ParseError::TooManyElements {
+                line,
+                column,
+                limit,
+            } => write!(
+                f,
+                "❌ Syntax error {}: Too many elements (the limit is {})",
+                at(line, column),
+                limit
+            ),
+            ParseError::IntegerOverflow { line, column, text } => write!(
+                f,
+                "❌ Syntax error {}: Integer '{}' is out of range for i64",
+                at(line, column),
+                text
+            ),
+            ParseError::DuplicateKey {
+                line,
+                column,
+                key,
+                first_line,
+            } => write!(
+                f,
+                "❌ Error {}: Duplicate key '{}' (first defined on line {})",
+                at(line, column),
+                key,
+                first_line
+            ),
+            ParseError::UndefinedVariable { line, column, name } => write!(
+                f,
+                "❌ Error {}: Environment variable '{}' is not set",
+                at(line, column),
+                name
+            ),
+            ParseError::InvalidInclude {
+                line,
+                column,
+                path,
+                reason,
+            } => write!(
+                f,
+                "❌ Error {}: Cannot include '{}': {}",
+                at(line, column),
+                path,
+                reason
+            ),
+            ParseError::InFile { path, error } => error.write_message(f, Some(path)),
+            ParseError::InputTooLarge { line, limit } => write!(
+                f,
+                "❌ Error: Input is longer than {} bytes (line {})",
+                limit, line
+            ),
+            ParseError::TooManyLines { limit } => {
+                write!(f, "❌ Error: Input has more than {} lines", limit)
+            }
+            ParseError::InvalidUtf8 { offset } => {
+                write!(f, "❌ Error: Invalid UTF-8 at byte offset {}", offset)
+            }
+            ParseError::InvalidSeparator { separator } => write!(
+                f,
+                "❌ Error: Invalid separator '{}'; it must not be empty or hold whitespace, key characters, quotes, brackets or comment markers",
+                separator
+            ),
+            ParseError::Io { path, .. } => {
+                write!(f, "❌ Error: Could not read file '{}'", path)
+            }
+        }
+    }
+}
+
+/// Where an error occurred: `on line 3, col 1`, prefixed by `in 'file' ` when
+/// the file is known.
+struct Location<'a> {
+    path: Option<&'a str>,
+    line: usize,
+    column: usize,
+}
+
+impl fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = self.path {
+            write!(f, "in '{}' ", path)?;
+        }
+        write!(f, "on line {}, col {}", self.line, self.column)
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io { source, .. } => Some(source),
+            ParseError::InFile { error, .. } => error.source(),
+            _ => None,
+        }
+    }
+}
+
+/// An error converting a `Value` into a Rust type of a different variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError {
+    /// The variant the conversion needed, e.g. `"integer"`.
+    pub expected: &'static str,
+    /// The variant the value actually was.
+    pub found: &'static str,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "❌ Error: Expected {}, found {}",
+            self.expected, self.found
+        )
+    }
+}
+
+impl Error for ConversionError {}
+
+/// A section that [`to_thtc`](crate::ThethaCoreConfig::to_thtc) can't write,
+/// because a name in its path is empty or starts or ends with whitespace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnwritableSection {
+    pub section: String,
+}
+
+impl fmt::Display for UnwritableSection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "❌ Error: Section '{}' has an empty or padded name and can't be written",
+            self.section
+        )
+    }
+}
+
+impl Error for UnwritableSection {}
+
+/// Why [`rename_section`](crate::ThethaCoreConfig::rename_section) or
+/// [`rename_section_tree`](crate::ThethaCoreConfig::rename_section_tree)
+/// failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameError {
+    /// There is no section to move.
+    NotFound { section: String },
+    /// A section already has the new name.
+    AlreadyExists { section: String },
+    /// A tree of sections would move under itself, as when renaming `"db"` to
+    /// `"db/replica"`.
+    IntoItself { from: String, to: String },
+}
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenameError::NotFound { section } => {
+                write!(f, "❌ Error: Section '{}' does not exist", section)
+            }
+            RenameError::AlreadyExists { section } => {
+                write!(f, "❌ Error: Section '{}' already exists", section)
+            }
+            RenameError::IntoItself { from, to } => write!(
+                f,
+                "❌ Error: Cannot move section '{}' to '{}', inside itself",
+                from, to
+            ),
+        }
+    }
+}
+
+impl Error for RenameError {}
+
+/// An error deserializing a configuration into a Rust type with
+/// [`from_str`](crate::from_str) or [`from_config`](crate::from_config).
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum DeserializeError {
+    /// The input could not be parsed.
+    Parse(ParseError),
+    /// The configuration doesn't fit the target type, e.g. a missing field.
+    Message(String),
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeserializeError::Parse(err) => err.fmt(f),
+            DeserializeError::Message(msg) => write!(f, "❌ Error: {}", msg),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Error for DeserializeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DeserializeError::Parse(err) => Some(err),
+            DeserializeError::Message(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::de::Error for DeserializeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeserializeError::Message(msg.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<ParseError> for DeserializeError {
+    fn from(err: ParseError) -> Self {
+        DeserializeError::Parse(err)
+    }
+}
+
+/// A way in which a configuration doesn't match a [`Schema`](crate::validator::Schema).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// A required key is absent.
+    Missing { section: String, key: String },
+    /// A key holds a different kind of value than the schema expects.
+    WrongKind {
+        section: String,
+        key: String,
+        expected: ValueKind,
+        found: ValueKind,
+    },
+    /// An array element holds a different kind of value than the schema
+    /// expects; `index` is that of the first such element.
+    WrongElementKind {
+        section: String,
+        key: String,
+        index: usize,
+        expected: ValueKind,
+        found: ValueKind,
+    },
+    /// A number falls outside the range the schema allows.
+    OutOfRange {
+        section: String,
+        key: String,
+        value: f64,
+        range: RangeInclusive<f64>,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::Missing { section, key } => write!(
+                f,
+                "❌ Error: Missing required key '{}' in section '{}'",
+                key, section
+            ),
+            ValidationError::WrongKind {
+                section,
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "❌ Error: Expected {} for '{}/{}', found {}",
+                expected, section, key, found
+            ),
+            ValidationError::WrongElementKind {
+                section,
+                key,
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "❌ Error: Expected {} at index {} of '{}/{}', found {}",
+                expected, index, section, key, found
+            ),
+            ValidationError::OutOfRange {
+                section,
+                key,
+                value,
+                range,
+            } => write!(
+                f,
+                "❌ Error: Value {} of '{}/{}' is outside {}..={}",
+                value,
+                section,
+                key,
+                range.start(),
+                range.end()
+            ),
+        }
+    }
+}
+
+impl Error for ValidationError {}
@@ -0,0 +1,638 @@
+use crate::document::Comments;
+use crate::errors::UnwritableSection;
+use crate::parser::{byte_unit, is_bare_key, ThethaCoreConfig, Value};
+use indexmap::IndexMap;
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Render a section path (e.g. "database/advanced") as a section header,
+/// escaping `<`, `>`, `\` and the comment markers `#` and `;` in the names.
+pub(crate) fn format_header(section: &str) -> String {
+    let parts: Vec<String> = section
+        .split('/')
+        .map(|name| {
+            name.chars()
+                .fold(String::with_capacity(name.len()), |mut out, c| {
+                    if matches!(c, '<' | '>' | '\\' | '#' | ';') {
+                        out.push('\\');
+                    }
+                    out.push(c);
+                    out
+                })
+        })
+        .collect();
+    if parts.len() == 1 {
+        format!("<{}>", parts[0])
+    } else {
+        format!("<{}>>", parts.join("<"))
+    }
+}
+
+/// Whether the header of `section` reads back as the same path: none of its
+/// names may be empty or start or end with whitespace, which the parser trims.
+pub(crate) fn is_writable_section(section: &str) -> bool {
+    section
+        .split('/')
+        .all(|name| !name.is_empty() && name.trim() == name)
+}
+
+/// Write `section` under the header of `name`, putting each comment line
+/// kept in `comments`, if given, before its header or key.
+pub(crate) fn write_section(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    section: &IndexMap<String, Value>,
+    comments: Option<&Comments>,
+) -> fmt::Result {
+    for comment in comments.map_or(&[][..], |c| c.section(name)) {
+        writeln!(f, "{}", comment)?;
+    }
+    writeln!(f, "{}", format_header(name))?;
+    for (key, value) in section {
+        for comment in comments.map_or(&[][..], |c| c.key(name, key)) {
+            writeln!(f, "{}", comment)?;
+        }
+        writeln!(f, "{} == {}", format_key(key), value)?;
+    }
+    Ok(())
+}
+
+/// Render a key, quoting it unless it can be written bare.
+pub(crate) fn format_key(key: &str) -> Cow<'_, str> {
+    if is_bare_key(key) {
+        Cow::Borrowed(key)
+    } else {
+        Cow::Owned(format!("\"{}\"", escape(key)))
+    }
+}
+
+/// Escape a string so the parser reads it back unchanged.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Render a duration as unit components, largest first, e.g. `1h30m`.
+fn format_duration(d: &Duration) -> String {
+    let secs = d.as_secs();
+    let nanos = d.subsec_nanos() as u64;
+    let components = [
+        (secs / 86_400, "d"),
+        (secs / 3_600 % 24, "h"),
+        (secs / 60 % 60, "m"),
+        (secs % 60, "s"),
+        (nanos / 1_000_000, "ms"),
+        (nanos / 1_000 % 1_000, "us"),
+        (nanos % 1_000, "ns"),
+    ];
+    let out: String = components
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, unit)| format!("{}{}", n, unit))
+        .collect();
+    if out.is_empty() {
+        "0s".to_string()
+    } else {
+        out
+    }
+}
+
+/// Render a byte count with the largest unit that divides it exactly, e.g.
+/// `512KiB`, falling back to plain bytes like `1500B`.
+fn format_bytes(n: u64) -> String {
+    ["GiB", "GB", "MiB", "MB", "KiB", "KB"]
+        .into_iter()
+        .find_map(|unit| {
+            let size = byte_unit(unit).unwrap();
+            (n > 0 && n.is_multiple_of(size)).then(|| format!("{}{}", n / size, unit))
+        })
+        .unwrap_or_else(|| format!("{}B", n))
+}
+
+/// Render `value` with every non-empty array and object spread over
+/// indented lines, two spaces per level of `depth`.
+fn write_pretty(out: &mut String, value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth + 1);
+    match value {
+        Value::Array(items) if !items.is_empty() => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&indent);
+                write_pretty(out, item, depth + 1);
+            }
+            out.push('\n');
+            out.push_str(&"  ".repeat(depth));
+            out.push(']');
+        }
+        Value::Object(object) if !object.is_empty() => {
+            out.push_str("{\n");
+            for (i, (key, item)) in object.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&format!("{}\"{}\" == ", indent, escape(key)));
+                write_pretty(out, item, depth + 1);
+            }
+            out.push('\n');
+            out.push_str(&"  ".repeat(depth));
+            out.push('}');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// Renders a value in the syntax accepted by the parser, e.g. `"text"`,
+/// `True`, `Null`, `[1, 2]` or `{ "key" == 1 }`.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "\"{}\"", escape(s)),
+            Value::Integer(n) => write!(f, "{}", n),
+            // Debug formatting keeps the decimal point, so `1.0` doesn't come back as an integer.
+            Value::Float(n) => write!(f, "{:?}", n),
+            Value::Boolean(true) => f.write_str("True"),
+            Value::Boolean(false) => f.write_str("False"),
+            Value::Null => f.write_str("Null"),
+            Value::Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            Value::Object(object) if object.is_empty() => f.write_str("{}"),
+            Value::Object(object) => {
+                f.write_str("{ ")?;
+                for (i, (key, value)) in object.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "\"{}\" == {}", escape(key), value)?;
+                }
+                f.write_str(" }")
+            }
+            Value::Duration(d) => f.write_str(&format_duration(d)),
+            Value::Bytes(n) => f.write_str(&format_bytes(*n)),
+            #[cfg(feature = "chrono")]
+            Value::DateTime(t) => {
+                f.write_str(&t.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true))
+            }
+        }
+    }
+}
+
+/// Serializes the configuration back into ThethaCore syntax.
+///
+/// Sections and keys are written in the order they were parsed or inserted.
+/// A section path with an empty name, or one starting or ending with
+/// whitespace, is still written, but doesn't read back the same; use
+/// [`ThethaCoreConfig::to_thtc`] to catch this.
+impl fmt::Display for ThethaCoreConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, section)) in self.sections.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write_section(f, name, section, None)?;
+        }
+        Ok(())
+    }
+}
+
+impl ThethaCoreConfig {
+    /// Serialize the configuration like its `Display` output, but with arrays
+    /// and objects written over several lines, indented two spaces per level.
+    ///
+    /// The output parses back to the same configuration, unless a section
+    /// name can't be written, as for `Display`.
+    pub fn to_string_pretty(&self) -> String {
+        let mut out = String::new();
+        for (i, (name, section)) in self.sections.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            out.push_str(&format_header(name));
+            out.push('\n');
+            for (key, value) in section {
+                out.push_str(&format_key(key));
+                out.push_str(" == ");
+                write_pretty(&mut out, value, 0);
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// Serialize the configuration like its `Display` output, failing if a
+    /// section path has an empty name or one starting or ending with
+    /// whitespace, which no header reads back as.
+    pub fn to_thtc(&self) -> Result<String, UnwritableSection> {
+        match self.sections.keys().find(|name| !is_writable_section(name)) {
+            Some(name) => Err(UnwritableSection {
+                section: name.clone(),
+            }),
+            None => Ok(self.to_string()),
+        }
+    }
+
+    /// Write the configuration to a file path, failing like
+    /// [`to_thtc`](Self::to_thtc) if a section can't be written.
+    ///
+    /// The output is written to a new temporary file next to `path`, flushed to
+    /// disk and then renamed over it, so an interrupted write never leaves a
+    /// half-written config behind. The temporary file is removed if any step
+    /// fails.
+    pub fn write_to_file(&self, path: &str) -> Result<(), String> {
+        let content = self.to_thtc().map_err(|err| {
+            format!(
+                "❌ Error: Could not write file '{}': section '{}' has an empty or padded name",
+                path, err.section
+            )
+        })?;
+        let target = Path::new(path);
+        let (mut file, tmp_path) = create_temp_file(target)
+            .map_err(|_| format!("❌ Error: Could not write file '{}'", path))?;
+        let written = file
+            .write_all(content.as_bytes())
+            .and_then(|()| file.sync_all());
+        drop(file);
+        written
+            .and_then(|()| fs::rename(&tmp_path, target))
+            .map_err(|_| {
+                let _ = fs::remove_file(&tmp_path);
+                format!("❌ Error: Could not write file '{}'", path)
+            })
+    }
+}
+
+/// Create a temporary file in the directory of `target`, named after it but
+/// not used by any other file.
+fn create_temp_file(target: &Path) -> io::Result<(File, PathBuf)> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let name = target
+        .file_name()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?
+        .to_string_lossy();
+    let dir = target.parent().unwrap_or(Path::new(""));
+    loop {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let tmp_path = dir.join(format!(".{}.{}.{}.tmp", name, process::id(), n));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp_path)
+        {
+            Ok(file) => return Ok((file, tmp_path)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConfigBuilder, Document};
+    use indexmap::IndexMap;
+
+    #[test]
+    fn test_to_string_format() {
+        let input = r#"
+        <general>
+        app_name == "MyApp"
+        version == 1.0
+
+        <database<advanced>>
+        options == ["fast", "secure"]
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.to_string(),
+            "<general>\napp_name == \"MyApp\"\nversion == 1.0\n\n\
+             <database<advanced>>\noptions == [\"fast\", \"secure\"]\n"
+        );
+    }
+
+    #[test]
+    fn test_to_string_preserves_order() {
+        let input = r#"
+        <zeta>
+        b == 1
+        a == { "y" == 1, "x" == 2 }
+
+        <alpha>
+        d == 3
+        c == 4
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.to_string(),
+            "<zeta>\nb == 1\na == { \"y\" == 1, \"x\" == 2 }\n\n<alpha>\nd == 3\nc == 4\n"
+        );
+    }
+
+    #[test]
+    fn test_value_display() {
+        assert_eq!(
+            Value::String("say \"hi\"".to_string()).to_string(),
+            r#""say \"hi\"""#
+        );
+        assert_eq!(Value::Integer(-3).to_string(), "-3");
+        assert_eq!(Value::Float(2.0).to_string(), "2.0");
+        assert_eq!(Value::Boolean(true).to_string(), "True");
+        assert_eq!(Value::Boolean(false).to_string(), "False");
+        assert_eq!(Value::Null.to_string(), "Null");
+        assert_eq!(Value::Array(vec![]).to_string(), "[]");
+
+        let nested = Value::Array(vec![
+            Value::Integer(1),
+            Value::Object(IndexMap::from([(
+                "k".to_string(),
+                Value::Array(vec![Value::Null]),
+            )])),
+        ]);
+        assert_eq!(nested.to_string(), r#"[1, { "k" == [Null] }]"#);
+    }
+
+    #[test]
+    fn test_to_string_pretty() {
+        let input = r#"
+        <app>
+        name == "demo"
+        db == { "pool" == { "min" == 1, "max" == 8 }, "hosts" == ["a", "b"], "extra" == {} }
+        empty == []
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.to_string_pretty(),
+            r#"<app>
+name == "demo"
+db == {
+  "pool" == {
+    "min" == 1,
+    "max" == 8
+  },
+  "hosts" == [
+    "a",
+    "b"
+  ],
+  "extra" == {}
+}
+empty == []
+"#
+        );
+        let reparsed = ThethaCoreConfig::parse(&config.to_string_pretty()).unwrap();
+        assert_eq!(config.sections, reparsed.sections);
+    }
+
+    #[test]
+    fn test_duration_display() {
+        assert_eq!(Value::Duration(Duration::from_secs(30)).to_string(), "30s");
+        assert_eq!(
+            Value::Duration(Duration::from_secs(5400)).to_string(),
+            "1h30m"
+        );
+        assert_eq!(
+            Value::Duration(Duration::new(93_784, 5_006_007)).to_string(),
+            "1d2h3m4s5ms6us7ns"
+        );
+        assert_eq!(Value::Duration(Duration::ZERO).to_string(), "0s");
+
+        let config = ThethaCoreConfig::parse("<t>\na == 90m\nb == 1500ms").unwrap();
+        let reparsed = ThethaCoreConfig::parse(&config.to_string()).unwrap();
+        assert_eq!(config.sections, reparsed.sections);
+    }
+
+    #[test]
+    fn test_bytes_display() {
+        assert_eq!(Value::Bytes(10_000_000).to_string(), "10MB");
+        assert_eq!(Value::Bytes(512 * 1024).to_string(), "512KiB");
+        assert_eq!(Value::Bytes(3 << 30).to_string(), "3GiB");
+        assert_eq!(Value::Bytes(1500).to_string(), "1500B");
+        assert_eq!(Value::Bytes(0).to_string(), "0B");
+
+        let config = ThethaCoreConfig::parse("<l>\na == 10MB\nb == 2KiB\nc == 7B").unwrap();
+        let reparsed = ThethaCoreConfig::parse(&config.to_string()).unwrap();
+        assert_eq!(config.sections, reparsed.sections);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let input = std::fs::read_to_string("example.thtc").unwrap();
+        let config = ThethaCoreConfig::parse(&input).unwrap();
+        let reparsed = ThethaCoreConfig::parse(&config.to_string()).unwrap();
+        assert_eq!(config.sections, reparsed.sections);
+    }
+
+    #[test]
+    fn test_round_trip_empty_containers() {
+        let input = r#"
+        <empty>
+
+        <data>
+        items == []
+        object == {}
+        nothing == Null
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let reparsed = ThethaCoreConfig::parse(&config.to_string()).unwrap();
+        assert_eq!(config.sections, reparsed.sections);
+    }
+
+    #[test]
+    fn test_round_trip_escaped_strings() {
+        let input = r#"
+        <data>
+        msg == "say \"hi\"\n\tand \\ bye"
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let reparsed = ThethaCoreConfig::parse(&config.to_string()).unwrap();
+        assert_eq!(config.sections, reparsed.sections);
+    }
+
+    #[test]
+    fn test_round_trip_escaped_section_names() {
+        let input = r#"
+        <a\<b>
+        x == 1
+
+        <vec\<u8\>\\<inner>>
+        y == 2
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.sorted_section_names(), ["a<b", "vec<u8>\\/inner"]);
+        let output = config.to_string();
+        assert!(output.contains("<a\\<b>\n"));
+        assert!(output.contains("<vec\\<u8\\>\\\\<inner>>\n"));
+        let reparsed = ThethaCoreConfig::parse(&output).unwrap();
+        assert_eq!(config.sections, reparsed.sections);
+
+        // Comment markers are escaped; quotes and brackets need no escaping.
+        let mut config = ThethaCoreConfig::new();
+        for name in ["c#/d;e", "it's [\"x\"]", "f ;; g", "\"\"\"", "a*/b/*c"] {
+            config
+                .section_entry(name)
+                .insert("x".to_string(), Value::Integer(1));
+        }
+        let output = config.to_string();
+        assert!(output.contains("<c\\#<d\\;e>>\n"));
+        assert!(output.contains("<it's [\"x\"]>\n"));
+        let reparsed = ThethaCoreConfig::parse(&output).unwrap();
+        assert_eq!(config.sections, reparsed.sections);
+        assert_eq!(
+            ThethaCoreConfig::parse(&config.to_string_pretty()).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_unwritable_section_names() {
+        for name in ["", "a//b", "a/", " a", "a/b "] {
+            let mut config = ThethaCoreConfig::new();
+            config.section_entry(name);
+            assert_eq!(
+                config.to_thtc(),
+                Err(UnwritableSection {
+                    section: name.to_string()
+                })
+            );
+            let err = config.write_to_file("unused.thtc").unwrap_err();
+            assert!(err.contains("has an empty or padded name"), "{err}");
+        }
+        assert!(!std::path::Path::new("unused.thtc").exists());
+    }
+
+    #[test]
+    fn test_display_never_fails() {
+        // Every public way of making a section with an unwritable name.
+        let mut configs = vec![
+            ThethaCoreConfig::from_json(r#"{"": {"a": 1}}"#).unwrap(),
+            ThethaCoreConfig::from_json(r#"{" x": {"a": 1}}"#).unwrap(),
+            ConfigBuilder::new().section("").set("a", 1).build(),
+        ];
+        let mut config = ThethaCoreConfig::parse("<a>\nx == 1").unwrap();
+        config.rename_section("a", "a/ ").unwrap();
+        configs.push(config);
+        let mut config = ThethaCoreConfig::new();
+        config.section_entry("//");
+        configs.push(config);
+
+        for config in configs {
+            assert!(config.to_thtc().is_err());
+            assert!(config.to_string().starts_with('<'));
+            assert!(config.to_string_pretty().starts_with('<'));
+            assert!(Document::from(config).to_string().starts_with('<'));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_quoted_keys() {
+        let input = r#"
+        <headers>
+        "Content Type" == "application/json"
+        "say \"hi\"" == 1
+        "plain-key.x" == 2
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let output = config.to_string();
+        assert!(output.contains("\"Content Type\" == \"application/json\""));
+        assert!(output.contains("\"say \\\"hi\\\"\" == 1"));
+        assert!(output.contains("\nplain-key.x == 2"));
+        let reparsed = ThethaCoreConfig::parse(&output).unwrap();
+        assert_eq!(config.sections, reparsed.sections);
+    }
+
+    #[test]
+    fn test_write_to_file() {
+        let dir = std::env::temp_dir().join(format!("thethac-write-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.thtc");
+        let path = path.to_str().unwrap();
+
+        let config = ThethaCoreConfig::parse_from_file("example.thtc").unwrap();
+        config.write_to_file(path).unwrap();
+        let reread = ThethaCoreConfig::parse_from_file(path).unwrap();
+        assert_eq!(config.sections, reread.sections);
+        assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_to_file_leaves_no_temp_files() {
+        let dir = std::env::temp_dir().join(format!("thethac-temp-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let files = || {
+            let mut names: Vec<String> = fs::read_dir(&dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+                .collect();
+            names.sort();
+            names
+        };
+        let target = dir.join("config.thtc");
+        let target = target.to_str().unwrap();
+        let config = ThethaCoreConfig::parse("<a>\nx == 1").unwrap();
+
+        // Successful writes, including one replacing the file, and several at
+        // once to the same target.
+        config.write_to_file(target).unwrap();
+        ThethaCoreConfig::new().write_to_file(target).unwrap();
+        assert_eq!(fs::read_to_string(target).unwrap(), "");
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| config.write_to_file(target).unwrap());
+            }
+        });
+        assert_eq!(ThethaCoreConfig::parse_from_file(target).unwrap(), config);
+        assert_eq!(files(), ["config.thtc"]);
+
+        // The rename fails when the target is a directory.
+        fs::create_dir(dir.join("subdir")).unwrap();
+        assert!(config
+            .write_to_file(dir.join("subdir").to_str().unwrap())
+            .is_err());
+        // A name too long for the file system can't be created at all.
+        let long = dir.join("x".repeat(300));
+        assert!(config.write_to_file(long.to_str().unwrap()).is_err());
+        // A section name that can't be written fails before touching the disk.
+        let mut unwritable = config.clone();
+        unwritable.section_entry("");
+        assert!(unwritable.write_to_file(target).is_err());
+        assert_eq!(files(), ["config.thtc", "subdir"]);
+        assert_eq!(ThethaCoreConfig::parse_from_file(target).unwrap(), config);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_to_file_missing_dir() {
+        let config = ThethaCoreConfig::new();
+        let err = config
+            .write_to_file("/nonexistent-thethac-dir/config.thtc")
+            .unwrap_err();
+        assert!(err.starts_with("❌ Error: Could not write file"));
+    }
+}
@@ -0,0 +1,25 @@
+//! Parsing and querying of ThethaCore (`.thtc`) configuration files.
+
+pub mod config;
+pub mod converter;
+#[cfg(feature = "serde")]
+pub mod deserializer;
+pub mod document;
+pub mod errors;
+pub mod formatter;
+pub mod parser;
+pub mod validator;
+
+pub use config::{Change, ConfigBuilder, SectionBuilder};
+#[cfg(feature = "serde")]
+pub use deserializer::{from_config, from_str};
+pub use document::Document;
+#[cfg(feature = "serde")]
+pub use errors::DeserializeError;
+pub use errors::{ConversionError, ParseError, RenameError, UnwritableSection, ValidationError};
+pub use indexmap::IndexMap;
+pub use parser::{
+    ArrayMerge, ParserOptions, Spanned, ThethaCoreConfig, Value, ValueIndex, Warning,
+    DEFAULT_MAX_DEPTH, DEFAULT_SEPARATOR,
+};
+pub use validator::{Schema, ValueKind};
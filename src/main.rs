@@ -1,7 +1,7 @@
-mod parser;
+use thethac::ThethaCoreConfig;
 
 fn main() {
-    match parser::ThethaCoreConfig::parse_from_file("example.thtc") {
+    match ThethaCoreConfig::parse_from_file("example.thtc") {
         Ok(config) => println!("{:#?}", config),
         Err(e) => eprintln!("Error: {}", e),
     }
@@ -1,168 +1,2511 @@
+use crate::document::Comments;
+use crate::errors::ParseError;
+use indexmap::IndexMap;
 use regex::Regex;
-use std::collections::HashMap;
-use std::fs;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Read};
+use std::num::IntErrorKind;
+use std::ops::Index;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::Duration;
 
 /// Represents a value in a ThethaCore configuration.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
+    /// Text, written in quotes or, verbatim and unquoted, after a backtick, as
+    /// in `` version == `1.0 ``, which would otherwise be a float. A raw value
+    /// runs to the end of the line, or of its element in an array or object;
+    /// quotes in it are kept, and a comment only starts after whitespace.
     String(String),
     Integer(i64),
     Float(f64),
     Boolean(bool),
     Null,
     Array(Vec<Value>),
-    Object(HashMap<String, Value>),
+    Object(IndexMap<String, Value>),
+    /// A span of time written with unit suffixes, e.g. `30s` or `1h30m`.
+    Duration(Duration),
+    /// A byte count written with a unit suffix, e.g. `10MB` or `512KiB`.
+    Bytes(u64),
+    /// An unquoted RFC 3339 timestamp, e.g. `2024-01-15T09:30:00Z`, converted
+    /// to UTC.
+    #[cfg(feature = "chrono")]
+    DateTime(chrono::DateTime<chrono::Utc>),
 }
 
-/// Represents the entire ThethaCore configuration.
+impl Value {
+    /// Returns the string if this is a `Value::String`.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if this is a `Value::Integer`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the float if this is a `Value::Float`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer if this is a `Value::Integer` between `min` and
+    /// `max`, inclusive.
+    pub fn as_i64_in_range(&self, min: i64, max: i64) -> Option<i64> {
+        self.as_i64().filter(|n| (min..=max).contains(n))
+    }
+
+    /// Returns the float if this is a `Value::Float` between `min` and `max`,
+    /// inclusive. NaN is never in range.
+    pub fn as_f64_in_range(&self, min: f64, max: f64) -> Option<f64> {
+        self.as_f64().filter(|n| (min..=max).contains(n))
+    }
+
+    /// Returns the boolean if this is a `Value::Boolean`.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements if this is a `Value::Array`.
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Returns the map if this is a `Value::Object`.
+    pub fn as_object(&self) -> Option<&IndexMap<String, Value>> {
+        match self {
+            Value::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// Returns the duration if this is a `Value::Duration`.
+    pub fn as_duration(&self) -> Option<Duration> {
+        match self {
+            Value::Duration(d) => Some(*d),
+            _ => None,
+        }
+    }
+
+    /// The name of this value's variant, e.g. `"integer"`, for diagnostics.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Integer(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Boolean(_) => "boolean",
+            Value::Null => "null",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+            Value::Duration(_) => "duration",
+            Value::Bytes(_) => "bytes",
+            #[cfg(feature = "chrono")]
+            Value::DateTime(_) => "datetime",
+        }
+    }
+
+    /// Returns the byte count if this is a `Value::Bytes`.
+    pub fn as_bytes(&self) -> Option<u64> {
+        match self {
+            Value::Bytes(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Returns the timestamp if this is a `Value::DateTime`.
+    #[cfg(feature = "chrono")]
+    pub fn as_datetime(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        match self {
+            Value::DateTime(t) => Some(*t),
+            _ => None,
+        }
+    }
+
+    /// Whether this is a `Value::String`.
+    pub fn is_string(&self) -> bool {
+        matches!(self, Value::String(_))
+    }
+
+    /// Whether this is a `Value::Integer`.
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Value::Integer(_))
+    }
+
+    /// Whether this is a `Value::Float`.
+    pub fn is_float(&self) -> bool {
+        matches!(self, Value::Float(_))
+    }
+
+    /// Whether this is a `Value::Integer` or a `Value::Float`.
+    pub fn is_number(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+
+    /// Whether this is a `Value::Boolean`.
+    pub fn is_bool(&self) -> bool {
+        matches!(self, Value::Boolean(_))
+    }
+
+    /// Whether this is `Value::Null`.
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// Whether this is a `Value::Array`.
+    pub fn is_array(&self) -> bool {
+        matches!(self, Value::Array(_))
+    }
+
+    /// Whether this is a `Value::Object`.
+    pub fn is_object(&self) -> bool {
+        matches!(self, Value::Object(_))
+    }
+
+    /// Deep-merge `other` into this object.
+    ///
+    /// Fields of `other` are added, and replace existing fields, except that
+    /// nested objects are merged recursively and, with [`ArrayMerge::Concat`],
+    /// `other`'s arrays are appended to existing ones. If either side isn't an
+    /// object, the result is `other`.
+    pub fn merge_object(self, other: Value, arrays: ArrayMerge) -> Value {
+        match (self, other) {
+            (Value::Object(mut object), Value::Object(other)) => {
+                for (key, value) in other {
+                    match object.get_mut(&key) {
+                        Some(existing) => {
+                            let base = std::mem::replace(existing, Value::Null);
+                            *existing = match (base, value) {
+                                (Value::Array(mut items), Value::Array(more))
+                                    if arrays == ArrayMerge::Concat =>
+                                {
+                                    items.extend(more);
+                                    Value::Array(items)
+                                }
+                                (base, value) => base.merge_object(value, arrays),
+                            };
+                        }
+                        None => {
+                            object.insert(key, value);
+                        }
+                    }
+                }
+                Value::Object(object)
+            }
+            (_, other) => other,
+        }
+    }
+
+    /// Look up an object field by name or an array element by position,
+    /// returning `None` if there is none. `value[index]` does the same but
+    /// panics instead.
+    pub fn get<I: ValueIndex>(&self, index: I) -> Option<&Value> {
+        index.index_into(self)
+    }
+
+    /// Follow a dotted path such as `"servers.0.host"` down through nested
+    /// objects and arrays; numeric segments index into arrays. Returns `None`
+    /// if any segment is missing or the value at that point can't hold it.
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        path.split('.')
+            .try_fold(self, |value, segment| match value {
+                Value::Object(_) => value.get(segment),
+                Value::Array(_) => value.get(segment.parse::<usize>().ok()?),
+                _ => None,
+            })
+    }
+
+    /// Remove repeated elements from an array, keeping the first occurrence
+    /// of each. Does nothing if this isn't a `Value::Array`.
+    pub fn dedup_array(&mut self) {
+        if let Value::Array(items) = self {
+            let mut kept: Vec<Value> = Vec::with_capacity(items.len());
+            for item in items.drain(..) {
+                if !kept.contains(&item) {
+                    kept.push(item);
+                }
+            }
+            *items = kept;
+        }
+    }
+
+    /// Sort an array's elements. Values of different types are ordered null,
+    /// booleans, numbers (integers and floats compared by value), strings,
+    /// durations, byte sizes, datetimes, arrays and then objects; equal
+    /// elements keep their relative order. Does nothing if this isn't a
+    /// `Value::Array`.
+    pub fn sort_array(&mut self) {
+        if let Value::Array(items) = self {
+            items.sort_by(Value::total_cmp);
+        }
+    }
+
+    /// The order used by [`Value::sort_array`].
+    fn total_cmp(&self, other: &Value) -> Ordering {
+        fn rank(value: &Value) -> u8 {
+            match value {
+                Value::Null => 0,
+                Value::Boolean(_) => 1,
+                Value::Integer(_) | Value::Float(_) => 2,
+                Value::String(_) => 3,
+                Value::Duration(_) => 4,
+                Value::Bytes(_) => 5,
+                #[cfg(feature = "chrono")]
+                Value::DateTime(_) => 6,
+                Value::Array(_) => 7,
+                Value::Object(_) => 8,
+            }
+        }
+        match (self, other) {
+            (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+            (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+            (Value::Integer(a), Value::Float(b)) => (*a as f64).total_cmp(b),
+            (Value::Float(a), Value::Integer(b)) => a.total_cmp(&(*b as f64)),
+            (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Duration(a), Value::Duration(b)) => a.cmp(b),
+            (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+            #[cfg(feature = "chrono")]
+            (Value::DateTime(a), Value::DateTime(b)) => a.cmp(b),
+            (Value::Array(a), Value::Array(b)) => a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| a.total_cmp(b))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            (Value::Object(a), Value::Object(b)) => a
+                .iter()
+                .zip(b)
+                .map(|((ka, va), (kb, vb))| ka.cmp(kb).then_with(|| va.total_cmp(vb)))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+}
+
+/// A key or position that can look up a nested [`Value`]: a `&str` for object
+/// fields or a `usize` for array elements.
+pub trait ValueIndex {
+    /// The nested value, or `None` if `value` has no such field or element.
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value>;
+}
+
+impl ValueIndex for usize {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        value.as_array()?.get(*self)
+    }
+}
+
+impl ValueIndex for str {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        value.as_object()?.get(self)
+    }
+}
+
+impl ValueIndex for String {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        self.as_str().index_into(value)
+    }
+}
+
+impl<T: ValueIndex + ?Sized> ValueIndex for &T {
+    fn index_into<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        (**self).index_into(value)
+    }
+}
+
+/// Panics, like indexing a `Vec`, if there is no such field or element; use
+/// [`Value::get`] to handle that case.
+impl<I: ValueIndex + fmt::Debug> Index<I> for Value {
+    type Output = Value;
+
+    fn index(&self, index: I) -> &Value {
+        match index.index_into(self) {
+            Some(value) => value,
+            None => panic!("no index {:?} in {} value", index, self.type_name()),
+        }
+    }
+}
+
+/// Hashes consistently with `PartialEq`: object fields in any order, and
+/// `0.0` like `-0.0`.
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Value::String(s) => s.hash(state),
+            Value::Integer(n) => n.hash(state),
+            Value::Float(n) if *n == 0.0 => 0.0f64.to_bits().hash(state),
+            Value::Float(n) => n.to_bits().hash(state),
+            Value::Boolean(b) => b.hash(state),
+            Value::Null => {}
+            Value::Array(items) => items.hash(state),
+            Value::Object(object) => unordered_hash(object.iter()).hash(state),
+            Value::Duration(d) => d.hash(state),
+            Value::Bytes(n) => n.hash(state),
+            #[cfg(feature = "chrono")]
+            Value::DateTime(t) => t.hash(state),
+        }
+    }
+}
+
+/// Combine the hashes of `items` so that their order doesn't matter.
+pub(crate) fn unordered_hash<T: Hash>(items: impl Iterator<Item = T>) -> u64 {
+    items.fold(0u64, |acc, item| {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        acc.wrapping_add(hasher.finish())
+    })
+}
+
+/// How [`Value::merge_object`] combines two arrays under the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// The array from the other object wins.
+    Replace,
+    /// The other object's elements are appended.
+    Concat,
+}
+
+/// Delimiter of verbatim, possibly multi-line, strings.
+const TRIPLE_QUOTE: &str = "\"\"\"";
+
+/// Characters allowed in an unquoted key: word characters, `-` and `.`.
+const KEY_PATTERN: &str = r"[\w.-]+";
+
+/// A single- or double-quoted key, which may contain backslash escapes.
+const QUOTED_KEY_PATTERN: &str = r#""(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*'"#;
+
+/// Compiles a regex the first time it's used.
+macro_rules! lazy_regex {
+    ($pattern:expr) => {
+        LazyLock::new(|| Regex::new($pattern).unwrap())
+    };
+}
+
+static INCLUDE_REGEX: LazyLock<Regex> = lazy_regex!(r"^@include\s+(.+)$");
+static KV_REGEX: LazyLock<Regex> = LazyLock::new(|| kv_regex(DEFAULT_SEPARATOR));
+/// A key followed by a single `=`, a common slip for `==`.
+static SINGLE_EQUALS_REGEX: LazyLock<Regex> = lazy_regex!(&format!(
+    r#"^(?:{}|{})\s*(=)(?:[^=]|$)"#,
+    KEY_PATTERN, QUOTED_KEY_PATTERN
+));
+static VAR_REGEX: LazyLock<Regex> = lazy_regex!(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(?::-([^}]*))?\}");
+
+/// Check that `separator` can't be confused with a key, a value or a
+/// comment; see [`ParserOptions::separator`].
+fn check_separator(separator: &str) -> Result<(), ParseError> {
+    let ambiguous = |c: char| {
+        c.is_whitespace() || is_bare_key(c.encode_utf8(&mut [0; 4])) || "\"'`[]{}<>#;".contains(c)
+    };
+    if separator.is_empty()
+        || separator.contains(ambiguous)
+        || separator.contains("//")
+        || separator.contains("/*")
+    {
+        return Err(ParseError::InvalidSeparator {
+            separator: separator.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// A regex matching a key-value line with the given separator, capturing the
+/// key and the rest of the line after the separator.
+fn kv_regex(separator: &str) -> Regex {
+    Regex::new(&format!(
+        r#"^({}|{})\s*{}\s*(.*)$"#,
+        KEY_PATTERN,
+        QUOTED_KEY_PATTERN,
+        regex::escape(separator)
+    ))
+    .unwrap()
+}
+
+/// Whether `key` can be written without quotes, i.e. matches [`KEY_PATTERN`].
+pub(crate) fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// A parsed item together with where it starts in the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+}
+
+/// Something suspicious but harmless found while parsing, reported by
+/// [`ThethaCoreConfig::parse_with_warnings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// 1-based line number.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "⚠️ Warning on line {}: {}", self.line, self.message)
+    }
+}
+
+/// The `(line, column)` of each value, by section and key.
+type SpanMap = IndexMap<String, IndexMap<String, (usize, usize)>>;
+
+/// What [`ThethaCoreConfig::parse_inner`] may use and collect besides the
+/// configuration itself. Each part is only used if given.
+#[derive(Default)]
+pub(crate) struct ParseContext<'a> {
+    /// The directory to resolve `@include` directives from and the files
+    /// included so far; includes aren't allowed without it.
+    pub(crate) includes: Option<(&'a Path, &'a mut HashSet<PathBuf>)>,
+    /// Where to record the line and column of every value.
+    pub(crate) spans: Option<&'a mut SpanMap>,
+    /// Where to add warnings.
+    pub(crate) warnings: Option<&'a mut Vec<Warning>>,
+    /// Where to keep comment lines.
+    pub(crate) comments: Option<&'a mut Comments>,
+    /// Where to add each finished document when `---` lines separate
+    /// documents; the last is returned.
+    pub(crate) documents: Option<&'a mut Vec<ThethaCoreConfig>>,
+}
+
+/// Flags controlling how a configuration is parsed.
+///
+/// `ParserOptions::default()` gives the behavior of [`ThethaCoreConfig::parse`].
 #[derive(Debug, Clone)]
+pub struct ParserOptions {
+    /// Reject a key that is defined twice in the same section instead of
+    /// letting the last definition win. Keys from an `@include`d file count as
+    /// defined on the line of the directive, so they may not redefine keys
+    /// before it or be redefined after it.
+    pub strict_duplicates: bool,
+    /// Require every section to be closed with a `</name>` marker before the
+    /// next section header or the end of the input. Without this, close
+    /// markers are optional, but must match the open section when present.
+    pub strict_sections: bool,
+    /// Replace `${VAR}` and `${VAR:-fallback}` in string values with the
+    /// contents of the environment variable `VAR`.
+    pub interpolate_env: bool,
+    /// Also accept `true/false`, `yes/no` and `on/off`, in any case, as
+    /// booleans, and `null` in any case for `Null`. By default only `True`,
+    /// `False` and `Null` are.
+    pub lenient_booleans: bool,
+    /// How deeply arrays and objects may be nested inside a value; deeper
+    /// input is rejected rather than risking a stack overflow. Defaults to
+    /// [`DEFAULT_MAX_DEPTH`].
+    pub max_depth: usize,
+    /// The most bytes a file or input may hold, not counting line breaks.
+    /// Unlimited if `None`, the default.
+    pub max_input_len: Option<usize>,
+    /// The most lines a file or input may hold. Unlimited if `None`, the
+    /// default.
+    pub max_lines: Option<usize>,
+    /// The most elements a single array, or fields a single object, may hold.
+    /// Unlimited if `None`, the default.
+    pub max_elements: Option<usize>,
+    /// Reject a section header that no key follows, so that a typo leaving a
+    /// section empty is caught. By default such sections are kept, empty.
+    pub require_non_empty_sections: bool,
+    /// What separates a key from its value, e.g. `":"` for `key: value`.
+    /// Defaults to [`DEFAULT_SEPARATOR`]. Parsing fails with
+    /// [`ParseError::InvalidSeparator`] if it's empty or holds whitespace,
+    /// characters allowed in bare keys, quotes, brackets or comment markers,
+    /// which would make lines ambiguous. Only key-value lines use it: the
+    /// fields of objects are still written `"key" == value`.
+    pub separator: String,
+}
+
+/// Options are set with chained calls, e.g.
+/// `ParserOptions::new().strict_duplicates(true).max_lines(10_000)`, or by
+/// assigning the public fields.
+impl ParserOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set [`strict_duplicates`](Self::strict_duplicates).
+    pub fn strict_duplicates(mut self, strict: bool) -> Self {
+        self.strict_duplicates = strict;
+        self
+    }
+
+    /// Set [`strict_sections`](Self::strict_sections).
+    pub fn strict_sections(mut self, strict: bool) -> Self {
+        self.strict_sections = strict;
+        self
+    }
+
+    /// Set [`interpolate_env`](Self::interpolate_env).
+    pub fn interpolate_env(mut self, interpolate: bool) -> Self {
+        self.interpolate_env = interpolate;
+        self
+    }
+
+    /// Set [`lenient_booleans`](Self::lenient_booleans).
+    pub fn lenient_booleans(mut self, lenient: bool) -> Self {
+        self.lenient_booleans = lenient;
+        self
+    }
+
+    /// Set [`max_depth`](Self::max_depth).
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Limit [`max_input_len`](Self::max_input_len) to `len` bytes.
+    pub fn max_input_len(mut self, len: usize) -> Self {
+        self.max_input_len = Some(len);
+        self
+    }
+
+    /// Limit [`max_lines`](Self::max_lines) to `lines`.
+    pub fn max_lines(mut self, lines: usize) -> Self {
+        self.max_lines = Some(lines);
+        self
+    }
+
+    /// Limit [`max_elements`](Self::max_elements) to `elements`.
+    pub fn max_elements(mut self, elements: usize) -> Self {
+        self.max_elements = Some(elements);
+        self
+    }
+
+    /// Set [`require_non_empty_sections`](Self::require_non_empty_sections).
+    pub fn require_non_empty_sections(mut self, require: bool) -> Self {
+        self.require_non_empty_sections = require;
+        self
+    }
+
+    /// Set [`separator`](Self::separator).
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+}
+
+/// The default for [`ParserOptions::max_depth`].
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// The default for [`ParserOptions::separator`].
+pub const DEFAULT_SEPARATOR: &str = "==";
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            strict_duplicates: false,
+            strict_sections: false,
+            interpolate_env: false,
+            lenient_booleans: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_input_len: None,
+            max_lines: None,
+            max_elements: None,
+            require_non_empty_sections: false,
+            separator: DEFAULT_SEPARATOR.to_string(),
+        }
+    }
+}
+
+/// Represents the entire ThethaCore configuration.
+///
+/// Two configurations are equal when they hold the same sections and keys
+/// with equal values, regardless of order.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct ThethaCoreConfig {
     /// Keys are section paths (e.g., "database" or "database/advanced").
-    pub sections: HashMap<String, HashMap<String, Value>>,
+    /// Sections and their keys keep the order they appear in the input.
+    pub sections: IndexMap<String, IndexMap<String, Value>>,
 }
 
 impl ThethaCoreConfig {
     pub fn new() -> Self {
         Self {
-            sections: HashMap::new(),
+            sections: IndexMap::new(),
         }
     }
 
     /// Parse configuration from a file path.
-    pub fn parse_from_file(path: &str) -> Result<Self, String> {
-        let content = fs::read_to_string(path)
-            .map_err(|_| format!("❌ Error: Could not read file '{}'", path))?;
-        Self::parse(&content)
+    ///
+    /// The file may contain `@include "other.thtc"` lines, resolved relative to
+    /// the including file's directory. The included file is parsed on its own
+    /// and its sections are merged (see [`ThethaCoreConfig::merge`]) into the
+    /// config at the point of the directive, so keys that follow the directive
+    /// override included ones. Including the same file twice is an error, which
+    /// also rules out include cycles.
+    ///
+    /// Errors in the contents of a file are wrapped in [`ParseError::InFile`],
+    /// so their messages name the file, e.g. `in 'config/db.thtc' on line 12`.
+    pub fn parse_from_file(path: &str) -> Result<Self, ParseError> {
+        let mut included = HashSet::new();
+        Self::parse_file_inner(Path::new(path), &ParserOptions::default(), &mut included)
+    }
+
+    fn parse_file_inner(
+        path: &Path,
+        options: &ParserOptions,
+        included: &mut HashSet<PathBuf>,
+    ) -> Result<Self, ParseError> {
+        let io_error = |source| ParseError::Io {
+            path: path.display().to_string(),
+            source,
+        };
+        let file = File::open(path).map_err(io_error)?;
+        included.insert(fs::canonicalize(path).map_err(io_error)?);
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        let lines = BufReader::new(file)
+            .lines()
+            .map(|line| line.map_err(io_error));
+        let mut context = ParseContext {
+            includes: Some((base_dir, included)),
+            ..ParseContext::default()
+        };
+        Self::parse_inner(lines, options, &mut context).map_err(|error| {
+            match error {
+                // Read failures already name the file, errors from included
+                // files name the file they occurred in, and a bad separator
+                // isn't in any file.
+                ParseError::Io { .. }
+                | ParseError::InFile { .. }
+                | ParseError::InvalidSeparator { .. } => error,
+                error => ParseError::InFile {
+                    path: path.display().to_string(),
+                    error: Box::new(error),
+                },
+            }
+        })
+    }
+
+    /// Parse a configuration from any reader, such as stdin or a socket.
+    ///
+    /// The input is consumed a line at a time rather than read up front.
+    /// `@include` directives are not supported, as there is no directory to
+    /// resolve them from. Read failures are reported as [`ParseError::Io`]
+    /// with the path `<reader>`.
+    pub fn parse_from_reader<R: Read>(reader: R) -> Result<Self, ParseError> {
+        let lines = BufReader::new(reader).lines().map(|line| {
+            line.map_err(|source| ParseError::Io {
+                path: "<reader>".to_string(),
+                source,
+            })
+        });
+        Self::parse_inner(
+            lines,
+            &ParserOptions::default(),
+            &mut ParseContext::default(),
+        )
     }
 
     /// Parse a configuration from an input string.
-    pub fn parse(input: &str) -> Result<Self, String> {
+    ///
+    /// A section header may appear more than once; each block adds its keys
+    /// to the same section. If a key is defined twice in a section, whether in
+    /// one block or across several, the last definition wins.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        Self::parse_with_options(input, &ParserOptions::default())
+    }
+
+    /// Parse a configuration from raw bytes, which must be valid UTF-8.
+    ///
+    /// Invalid input is reported as [`ParseError::InvalidUtf8`] with the offset
+    /// of the first bad byte. Otherwise this behaves exactly like
+    /// [`ThethaCoreConfig::parse`].
+    pub fn parse_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        let input = std::str::from_utf8(bytes).map_err(|e| ParseError::InvalidUtf8 {
+            offset: e.valid_up_to(),
+        })?;
+        Self::parse(input)
+    }
+
+    /// Parse a configuration, rejecting keys defined twice in the same section,
+    /// including in separate blocks of a reopened section.
+    pub fn parse_strict(input: &str) -> Result<Self, ParseError> {
+        Self::parse_with_options(input, &ParserOptions::new().strict_duplicates(true))
+    }
+
+    /// Parse a configuration, substituting `${VAR}` references in string values
+    /// with environment variables.
+    ///
+    /// A reference to an unset variable is an error unless it gives a fallback,
+    /// as in `${VAR:-fallback}`.
+    pub fn parse_with_env(input: &str) -> Result<Self, ParseError> {
+        Self::parse_with_options(input, &ParserOptions::new().interpolate_env(true))
+    }
+
+    /// Parse a configuration from an input string using the given options.
+    pub fn parse_with_options(input: &str, options: &ParserOptions) -> Result<Self, ParseError> {
+        Self::parse_inner(input.lines().map(Ok), options, &mut ParseContext::default())
+    }
+
+    /// Parse several configurations from one input, separated by lines holding
+    /// only `---`.
+    ///
+    /// Each document is parsed on its own, as by [`ThethaCoreConfig::parse`],
+    /// so sections don't carry over from one to the next; a document may be
+    /// empty. Errors give line numbers within the whole input. A `---` inside
+    /// a multi-line string, array or object, or a block comment, is part of
+    /// it and doesn't separate documents.
+    pub fn parse_multi(input: &str) -> Result<Vec<Self>, ParseError> {
+        Self::parse_multi_with_options(input, &ParserOptions::default())
+    }
+
+    /// Like [`ThethaCoreConfig::parse_multi`], parsing each document with the
+    /// given options. Limits on the input's size apply to the whole input.
+    pub fn parse_multi_with_options(
+        input: &str,
+        options: &ParserOptions,
+    ) -> Result<Vec<Self>, ParseError> {
+        let mut documents = Vec::new();
+        let last = Self::parse_inner(
+            input.lines().map(Ok),
+            options,
+            &mut ParseContext {
+                documents: Some(&mut documents),
+                ..ParseContext::default()
+            },
+        )?;
+        documents.push(last);
+        Ok(documents)
+    }
+
+    /// Parse a configuration like [`ThethaCoreConfig::parse`], recording where
+    /// each value was defined.
+    ///
+    /// Returns the sections with every value wrapped in a [`Spanned`]. When a
+    /// key is defined more than once, the span is that of the definition that
+    /// wins.
+    pub fn parse_with_spans(
+        input: &str,
+    ) -> Result<IndexMap<String, IndexMap<String, Spanned<Value>>>, ParseError> {
+        let mut spans = SpanMap::new();
+        let config = Self::parse_inner(
+            input.lines().map(Ok),
+            &ParserOptions::default(),
+            &mut ParseContext {
+                spans: Some(&mut spans),
+                ..ParseContext::default()
+            },
+        )?;
+        Ok(config
+            .sections
+            .into_iter()
+            .map(|(name, section)| {
+                let section = section
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let (line, column) = spans[&name][&key];
+                        let spanned = Spanned {
+                            value,
+                            line,
+                            column,
+                        };
+                        (key, spanned)
+                    })
+                    .collect();
+                (name, section)
+            })
+            .collect())
+    }
+
+    /// Parse a configuration like [`ThethaCoreConfig::parse`], also returning
+    /// warnings about input that parses but probably isn't what was meant:
+    /// sections without any keys, and keys defined again in the same section.
+    ///
+    /// Warnings are ordered by line.
+    pub fn parse_with_warnings(input: &str) -> Result<(Self, Vec<Warning>), ParseError> {
+        let mut warnings = Vec::new();
+        let config = Self::parse_inner(
+            input.lines().map(Ok),
+            &ParserOptions::default(),
+            &mut ParseContext {
+                warnings: Some(&mut warnings),
+                ..ParseContext::default()
+            },
+        )?;
+        warnings.sort_by_key(|w| w.line);
+        Ok((config, warnings))
+    }
+
+    /// Read `.env`-style `KEY=VALUE` lines into `section`, creating it if needed.
+    ///
+    /// Every value is stored as a string, without a pair of surrounding single
+    /// or double quotes. Blank lines and `#` comments are skipped, and an
+    /// `export ` prefix on a line is ignored. Keys already in the section are
+    /// overwritten.
+    pub fn parse_env_block(&mut self, section: &str, text: &str) -> Result<(), ParseError> {
+        let mut entries = Vec::new();
+        for (line_num, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let indent = line[..line.len() - line.trim_start().len()].chars().count() + 1;
+            let pair = trimmed.strip_prefix("export ").unwrap_or(trimmed);
+            match pair.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() => {
+                    let value = value.trim();
+                    let value = ['"', '\'']
+                        .into_iter()
+                        .find_map(|q| value.strip_prefix(q)?.strip_suffix(q))
+                        .unwrap_or(value);
+                    entries.push((key.trim().to_string(), Value::String(value.to_string())));
+                }
+                _ => {
+                    return Err(ParseError::UnexpectedToken {
+                        line: line_num + 1,
+                        column: indent,
+                        text: trimmed.to_string(),
+                    })
+                }
+            }
+        }
+        self.sections
+            .entry(section.to_string())
+            .or_default()
+            .extend(entries);
+        Ok(())
+    }
+
+    /// Parse `raw_value` as a value, written as it would be after `==`, and
+    /// store it at `path`, creating the section if needed. This suits
+    /// command-line overrides such as `--set database.pool_size=20`.
+    ///
+    /// `path` is split at each `.` or `/`: the last part is the key and the
+    /// rest is the section path, so `"database.advanced.pool_size"` and
+    /// `"database/advanced/pool_size"` both set `pool_size` in
+    /// `database/advanced`. A part holding a `.` or `/` is written in double
+    /// quotes, with `\"` and `\\` for a quote and a backslash, as in
+    /// `app."v1.2"."log.level"`; section names can't hold a `/`, even quoted.
+    /// A path without a section or key, or with an empty part, is a
+    /// [`ParseError::ValueOutsideSection`]; a badly quoted one is a
+    /// [`ParseError::UnexpectedToken`] at the offending character. Errors
+    /// report positions within `raw_value`, or within `path`, on line 1.
+    pub fn set_from_str(&mut self, path: &str, raw_value: &str) -> Result<(), ParseError> {
+        let mut parts = split_override_path(path)?;
+        let key = parts.pop().unwrap_or_default();
+        if key.is_empty() || parts.is_empty() || parts.iter().any(String::is_empty) {
+            return Err(ParseError::ValueOutsideSection {
+                line: 1,
+                column: 1,
+                text: path.to_string(),
+            });
+        }
+        let trimmed = raw_value.trim_start();
+        let column = raw_value[..raw_value.len() - trimmed.len()].chars().count() + 1;
+        let value = parse_value(trimmed.trim_end(), &ParserOptions::default(), 1, column)?;
+        self.sections
+            .entry(parts.join("/"))
+            .or_default()
+            .insert(key, value);
+        Ok(())
+    }
+
+    /// Parse the lines of a configuration, using and filling in whatever
+    /// `context` provides.
+    pub(crate) fn parse_inner<L: AsRef<str>>(
+        lines: impl Iterator<Item = Result<L, ParseError>>,
+        options: &ParserOptions,
+        context: &mut ParseContext<'_>,
+    ) -> Result<Self, ParseError> {
+        let ParseContext {
+            includes,
+            spans,
+            warnings,
+            comments,
+            documents,
+        } = context;
+        check_separator(&options.separator)?;
         let mut config = ThethaCoreConfig::new();
         // Holds the current nested section names.
         let mut current_sections: Vec<String> = Vec::new();
+        // Path, line and column of the header of the section not yet closed
+        // by a `</name>` marker, if any.
+        let mut open_section: Option<(String, usize, usize)> = None;
+        let check_closed = |open_section: &Option<(String, usize, usize)>| match open_section {
+            Some((section, line, column)) if options.strict_sections => {
+                Err(ParseError::UnclosedSection {
+                    line: *line,
+                    column: *column,
+                    section: section.clone(),
+                })
+            }
+            _ => Ok(()),
+        };
+        // Matches key-value lines written with `options.separator`.
+        let custom_kv_regex =
+            (options.separator != DEFAULT_SEPARATOR).then(|| kv_regex(&options.separator));
+        let kv_regex = custom_kv_regex.as_ref().unwrap_or(&KV_REGEX);
+        // Line and column each section header first appeared on.
+        let mut header_lines: IndexMap<String, (usize, usize)> = IndexMap::new();
+        // Line each (section, key) pair was first defined on.
+        let mut first_seen: HashMap<(String, String), usize> = HashMap::new();
+        // Checks made once a document is complete.
+        let finish = |config: &Self,
+                      open_section: &Option<(String, usize, usize)>,
+                      header_lines: &IndexMap<String, (usize, usize)>| {
+            check_closed(open_section)?;
+            if options.require_non_empty_sections {
+                let empty = header_lines
+                    .iter()
+                    .find(|(section, _)| config.sections[*section].is_empty());
+                if let Some((section, &(line, column))) = empty {
+                    return Err(ParseError::EmptySection {
+                        line,
+                        column,
+                        section: section.clone(),
+                    });
+                }
+            }
+            Ok(())
+        };
 
-        let kv_regex = Regex::new(r"^(\w+)\s*==\s*(.+)$").unwrap();
-
-        for (line_num, line) in input.lines().enumerate() {
+        let (mut line_count, mut input_len) = (0, 0);
+        let lines = lines.map(|line| {
+            let line = line?;
+            line_count += 1;
+            input_len += line.as_ref().len();
+            if let Some(limit) = options.max_lines.filter(|&limit| line_count > limit) {
+                return Err(ParseError::TooManyLines { limit });
+            }
+            if let Some(limit) = options.max_input_len.filter(|&limit| input_len > limit) {
+                return Err(ParseError::InputTooLarge {
+                    line: line_count,
+                    limit,
+                });
+            }
+            Ok(line)
+        });
+        let mut lines = BlockComments::new(lines).enumerate();
+        while let Some((line_num, line)) = lines.next() {
+            let line = line?;
+            let mut line = line.as_str();
+            // Some editors start UTF-8 files with a byte order mark.
+            if line_num == 0 {
+                line = line.strip_prefix('\u{feff}').unwrap_or(line);
+            }
             let trimmed = line.trim();
+            // 1-based column of the first non-whitespace character.
+            let indent = line[..line.len() - line.trim_start().len()].chars().count() + 1;
+
+            if trimmed.is_empty() {
+                continue;
+            }
+            if let Some(documents) = documents.as_mut().filter(|_| trimmed == "---") {
+                finish(&config, &open_section, &header_lines)?;
+                documents.push(std::mem::take(&mut config));
+                current_sections.clear();
+                open_section = None;
+                header_lines.clear();
+                first_seen.clear();
+                continue;
+            }
+            if trimmed.starts_with("#") || trimmed.starts_with("//") || trimmed.starts_with(';') {
+                if let Some(comments) = comments.as_mut() {
+                    comments.pending.push(trimmed.to_string());
+                }
+                continue;
+            }
 
-            if trimmed.is_empty() || trimmed.starts_with("#") || trimmed.starts_with("//") {
+            if let Some(caps) = INCLUDE_REGEX.captures(trimmed) {
+                let target = caps.get(1).unwrap();
+                let target_col = column_at(trimmed, indent, target.start());
+                let target =
+                    match parse_value(target.as_str().trim(), options, line_num + 1, target_col)? {
+                        Value::String(target) => target,
+                        _ => {
+                            return Err(ParseError::InvalidValue {
+                                line: line_num + 1,
+                                column: target_col,
+                                text: target.as_str().to_string(),
+                                reason: "Include path must be a string".to_string(),
+                            })
+                        }
+                    };
+                let Some((base_dir, included)) = includes.as_mut() else {
+                    return Err(ParseError::InvalidInclude {
+                        line: line_num + 1,
+                        column: indent,
+                        path: target,
+                        reason: "Includes are only supported when parsing from a file".to_string(),
+                    });
+                };
+                let target_path = base_dir.join(&target);
+                let canonical =
+                    fs::canonicalize(&target_path).map_err(|source| ParseError::Io {
+                        path: target_path.display().to_string(),
+                        source,
+                    })?;
+                if included.contains(&canonical) {
+                    return Err(ParseError::InvalidInclude {
+                        line: line_num + 1,
+                        column: indent,
+                        path: target,
+                        reason: "File is already included".to_string(),
+                    });
+                }
+                let other = Self::parse_file_inner(&target_path, options, included)?;
+                // Included keys count as defined on the directive's line.
+                for (section, key, _) in other.iter() {
+                    let first_line = *first_seen
+                        .entry((section.to_string(), key.to_string()))
+                        .or_insert(line_num + 1);
+                    if options.strict_duplicates && first_line != line_num + 1 {
+                        return Err(ParseError::DuplicateKey {
+                            line: line_num + 1,
+                            column: indent,
+                            key: key.to_string(),
+                            first_line,
+                        });
+                    }
+                }
+                config.merge(&other);
                 continue;
             }
 
             if trimmed.starts_with('<') {
-                if trimmed.ends_with(">>") {
-                    let inner = &trimmed[1..trimmed.len()-2];
-                    current_sections = inner
-                        .split('<')
-                        .map(|s| s.trim().to_string())
-                        .collect();
-                } else if trimmed.ends_with('>') {
-                    let inner = &trimmed[1..trimmed.len()-1];
-                    current_sections = vec![inner.trim().to_string()];
-                } else {
-                    return Err(format!("❌ Syntax error on line {}: Invalid section header '{}'", line_num + 1, trimmed));
+                // As on value lines, a trailing `#`, `//` or `;` comment is dropped.
+                let header = strip_header_comment(trimmed);
+                if let Some(name) = header.strip_prefix("</").and_then(|h| h.strip_suffix('>')) {
+                    let name = unescape_section_name(name.trim());
+                    match open_section.take() {
+                        Some((section, ..)) if section == name => {}
+                        open => {
+                            return Err(ParseError::MismatchedSectionClose {
+                                line: line_num + 1,
+                                column: indent,
+                                found: name,
+                                open: open.map(|(section, ..)| section),
+                            })
+                        }
+                    }
+                    current_sections.clear();
+                    continue;
                 }
+                check_closed(&open_section)?;
+                current_sections = parse_section_header(header, line_num + 1, indent)?;
                 let section_key = current_sections.join("/");
-                config.sections.entry(section_key).or_insert(HashMap::new());
+                open_section = Some((section_key.clone(), line_num + 1, indent));
+                header_lines
+                    .entry(section_key.clone())
+                    .or_insert((line_num + 1, indent));
+                if let Some(comments) = comments.as_mut() {
+                    comments.attach_to_section(&section_key);
+                }
+                config.sections.entry(section_key).or_default();
                 continue;
             }
 
-            // Key-Value pair handling.
-            if let Some(caps) = kv_regex.captures(trimmed) {
-                let key = caps.get(1).unwrap().as_str().to_string();
-                let value_str = caps.get(2).unwrap().as_str().trim();
+            // Key-Value pair handling. A trailing `#`, `//` or `;` comment is dropped first.
+            if let Some(caps) = kv_regex.captures(strip_inline_comment(trimmed)) {
+                let key_match = caps.get(1).unwrap();
+                let key = match quoted_contents(key_match.as_str()) {
+                    Some(inner) => unescape(inner, line_num + 1, indent + 1)?,
+                    None => key_match.as_str().to_string(),
+                };
+                let value_match = caps.get(2).unwrap();
+                let value_col = column_at(trimmed, indent, value_match.start());
+                // Triple-quoted strings hide comment markers and, like arrays
+                // and objects, may span lines, so they are cut from the raw
+                // line rather than the stripped one.
+                let raw_value = &trimmed[value_match.start()..];
+                let value_str: Cow<str> = if raw_value.starts_with(TRIPLE_QUOTE) {
+                    Cow::Owned(read_triple_quoted(
+                        raw_value,
+                        &mut lines,
+                        line_num + 1,
+                        value_col,
+                    )?)
+                } else if raw_value.starts_with(['[', '{']) && bracket_depth(raw_value, 0) > 0 {
+                    Cow::Owned(read_container(
+                        raw_value,
+                        &mut lines,
+                        line_num + 1,
+                        value_col,
+                    )?)
+                } else {
+                    Cow::Borrowed(value_match.as_str().trim())
+                };
 
-                let value = parse_value(value_str, line_num + 1)?;
+                // `key ==` with nothing after it is present but empty.
+                let mut value = if value_str.is_empty() {
+                    Value::Null
+                } else {
+                    parse_value(&value_str, options, line_num + 1, value_col)?
+                };
+                if options.interpolate_env {
+                    interpolate_env(&mut value, line_num + 1, value_col)?;
+                }
 
                 // Ensure we're inside a section.
                 if current_sections.is_empty() {
-                    return Err(format!(
-                        "❌ Error on line {}: Key-value pair found outside of a section",
-                        line_num + 1
-                    ));
+                    return Err(ParseError::ValueOutsideSection {
+                        line: line_num + 1,
+                        column: indent,
+                        text: trimmed.to_string(),
+                    });
                 }
                 let section_key = current_sections.join("/");
-                if let Some(section) = config.sections.get_mut(&section_key) {
-                    section.insert(key, value);
-                } else {
-                    return Err(format!(
-                        "❌ Error on line {}: Section '{}' not initialized",
-                        line_num + 1,
-                        section_key
-                    ));
+                let first_line = *first_seen
+                    .entry((section_key.clone(), key.clone()))
+                    .or_insert(line_num + 1);
+                if options.strict_duplicates && first_line != line_num + 1 {
+                    return Err(ParseError::DuplicateKey {
+                        line: line_num + 1,
+                        column: indent,
+                        key,
+                        first_line,
+                    });
+                }
+                if let Some(warnings) = warnings.as_mut().filter(|_| first_line != line_num + 1) {
+                    warnings.push(Warning {
+                        line: line_num + 1,
+                        message: format!(
+                            "Key '{}' was already defined on line {}; the last definition wins",
+                            key, first_line
+                        ),
+                    });
+                }
+                if let Some(spans) = spans.as_mut() {
+                    spans
+                        .entry(section_key.clone())
+                        .or_default()
+                        .insert(key.clone(), (line_num + 1, value_col));
+                }
+                if let Some(comments) = comments.as_mut() {
+                    comments.attach_to_key(&section_key, &key);
                 }
+                config
+                    .sections
+                    .entry(section_key)
+                    .or_default()
+                    .insert(key, value);
+            } else if let Some(caps) = SINGLE_EQUALS_REGEX
+                .captures(trimmed)
+                .filter(|_| options.separator == DEFAULT_SEPARATOR)
+            {
+                return Err(ParseError::SingleEquals {
+                    line: line_num + 1,
+                    column: column_at(trimmed, indent, caps.get(1).unwrap().start()),
+                    text: trimmed.to_string(),
+                });
+            } else if trimmed.ends_with('>') {
+                // A header missing its opening `<`.
+                return Err(ParseError::UnterminatedSectionHeader {
+                    line: line_num + 1,
+                    column: indent,
+                    text: trimmed.to_string(),
+                });
             } else {
-                return Err(format!("❌ Syntax error on line {}: '{}'", line_num + 1, trimmed));
+                return Err(ParseError::UnexpectedToken {
+                    line: line_num + 1,
+                    column: indent,
+                    text: trimmed.to_string(),
+                });
             }
         }
 
+        finish(&config, &open_section, &header_lines)?;
+        if let Some(comments) = comments {
+            comments.trailing = std::mem::take(&mut comments.pending);
+        }
+        if let Some(warnings) = warnings {
+            for (section, (line, _)) in header_lines {
+                if config.sections[&section].is_empty() {
+                    warnings.push(Warning {
+                        line,
+                        message: format!("Section '{}' has no keys", section),
+                    });
+                }
+            }
+        }
         Ok(config)
     }
 }
 
-fn parse_value(value_str: &str, line_num: usize) -> Result<Value, String> {
-    let boolean_null_regex = Regex::new(r"^(True|False|Null)$").unwrap();
-    let array_regex = Regex::new(r"^\[(.*)\]$").unwrap();
-    let object_regex = Regex::new(r"^\{(.*)\}$").unwrap();
-
-    if boolean_null_regex.is_match(value_str) {
-        match value_str {
-            "True" => return Ok(Value::Boolean(true)),
-            "False" => return Ok(Value::Boolean(false)),
-            "Null" => return Ok(Value::Null),
-            _ => unreachable!(),
-        }
-    } else if value_str.starts_with('"') && value_str.ends_with('"') {
-        return Ok(Value::String(value_str[1..value_str.len()-1].to_string()));
-    } else if let Ok(num) = value_str.parse::<i64>() {
-        return Ok(Value::Integer(num));
-    } else if let Ok(num) = value_str.parse::<f64>() {
-        return Ok(Value::Float(num));
-    } else if let Some(caps) = array_regex.captures(value_str) {
-        let items_str = caps.get(1).unwrap().as_str();
-        let items: Result<Vec<Value>, String> = if items_str.trim().is_empty() {
-            Ok(vec![])
-        } else {
-            items_str.split(',')
-                .map(|s| parse_value(s.trim(), line_num))
+/// Split a section header such as `<database>` or `<database<advanced>>`
+/// into its section names. `\<`, `\>` and `\\` stand for the characters
+/// themselves, so `<a\<b>` is the section "a<b".
+fn parse_section_header(
+    header: &str,
+    line_num: usize,
+    col: usize,
+) -> Result<Vec<String>, ParseError> {
+    let body = header.strip_prefix('<').map(section_name_chars);
+    // Whether `chars` has the unescaped `c` at position `i`.
+    let is = |chars: &[(char, bool)], i: usize, c: char| chars.get(i) == Some(&(c, false));
+    let names: Vec<&[(char, bool)]> = match body.as_deref() {
+        Some(body)
+            if body.len() >= 2
+                && is(body, body.len() - 2, '>')
+                && is(body, body.len() - 1, '>') =>
+        {
+            body[..body.len() - 2]
+                .split(|&c| c == ('<', false))
                 .collect()
-        };
-        return items.map(Value::Array);
-    } else if let Some(caps) = object_regex.captures(value_str) {
-        let content = caps.get(1).unwrap().as_str();
-        let mut object = HashMap::new();
-        if content.trim().is_empty() {
-            return Ok(Value::Object(object));
         }
-        for pair in content.split(',') {
-            let kv: Vec<&str> = pair.split("==").map(|s| s.trim()).collect();
-            if kv.len() != 2 {
-                return Err(format!(
-                    "❌ Syntax error on line {}: Invalid object pair '{}'",
-                    line_num, pair
-                ));
+        // A single `>` closes a header holding one name, so `<a<b>` is the
+        // section "a<b".
+        Some(body) if !body.is_empty() && is(body, body.len() - 1, '>') => {
+            vec![&body[..body.len() - 1]]
+        }
+        _ => {
+            return Err(ParseError::UnterminatedSectionHeader {
+                line: line_num,
+                column: col,
+                text: header.to_string(),
+            })
+        }
+    };
+
+    if names.iter().any(|name| name.contains(&('>', false))) {
+        return Err(ParseError::InvalidSectionHeader {
+            line: line_num,
+            column: col,
+            text: header.to_string(),
+        });
+    }
+    let names: Vec<String> = names
+        .iter()
+        .map(|name| {
+            name.iter()
+                .map(|&(c, _)| c)
+                .collect::<String>()
+                .trim()
+                .to_string()
+        })
+        .collect();
+    if names.iter().any(|name| name.is_empty()) {
+        return Err(ParseError::EmptySectionName {
+            line: line_num,
+            column: col,
+            text: header.to_string(),
+        });
+    }
+    Ok(names)
+}
+
+/// The characters of a section header, each paired with whether it was
+/// written with a backslash escape.
+fn section_name_chars(text: &str) -> Vec<(char, bool)> {
+    let mut chars = text.chars().peekable();
+    let mut out = Vec::with_capacity(text.len());
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if c == '\\' && is_header_escape(next) => {
+                chars.next();
+                out.push((next, true));
             }
-            let key = if kv[0].starts_with('"') && kv[0].ends_with('"') {
-                &kv[0][1..kv[0].len()-1]
-            } else {
-                kv[0]
-            };
-            let val = parse_value(kv[1], line_num)?;
-            object.insert(key.to_string(), val);
+            _ => out.push((c, false)),
         }
-        return Ok(Value::Object(object));
     }
+    out
+}
 
-    Err(format!(
-        "❌ Syntax error on line {}: Unable to parse value '{}'",
-        line_num, value_str
-    ))
+/// Whether `\` before `c` escapes it in a section header.
+fn is_header_escape(c: char) -> bool {
+    matches!(c, '<' | '>' | '\\' | '#' | ';')
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Drop a trailing `#`, `//` or `;` comment from a section header line.
+/// Escaped characters don't start one, and quotes and brackets are part of the
+/// names, unlike on value lines.
+fn strip_header_comment(line: &str) -> &str {
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if chars
+                .peek()
+                .is_some_and(|&(_, next)| is_header_escape(next)) =>
+            {
+                chars.next();
+            }
+            _ if starts_comment(&line[i..]) => return line[..i].trim_end(),
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Read the name in a close marker such as `</a\>b>`, undoing escapes.
+fn unescape_section_name(name: &str) -> String {
+    section_name_chars(name)
+        .into_iter()
+        .map(|(c, _)| c)
+        .collect()
+}
+
+/// Split a [`ThethaCoreConfig::set_from_str`] path at each unquoted `.` or
+/// `/`, unquoting the parts. Parts before the last must be section names, so
+/// a quoted one may not hold a `/`.
+fn split_override_path(path: &str) -> Result<Vec<String>, ParseError> {
+    let unexpected = |index: usize| ParseError::UnexpectedToken {
+        line: 1,
+        column: path[..index].chars().count() + 1,
+        text: path.to_string(),
+    };
+    let mut parts = Vec::new();
+    let mut chars = path.char_indices().peekable();
+    loop {
+        let mut part = String::new();
+        let mut slash = None;
+        if let Some(&(start, '"')) = chars.peek() {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some((i, '\\')) => match chars.next() {
+                        Some((_, c @ ('"' | '\\'))) => part.push(c),
+                        _ => return Err(unexpected(i)),
+                    },
+                    Some((i, c)) => {
+                        if c == '/' {
+                            slash.get_or_insert(i);
+                        }
+                        part.push(c);
+                    }
+                    None => return Err(unexpected(start)),
+                }
+            }
+            match chars.peek() {
+                None | Some((_, '.' | '/')) => {}
+                Some(&(i, _)) => return Err(unexpected(i)),
+            }
+        } else {
+            while let Some(&(i, c)) = chars.peek() {
+                match c {
+                    '.' | '/' => break,
+                    '"' => return Err(unexpected(i)),
+                    _ => part.push(c),
+                }
+                chars.next();
+            }
+        }
+        match chars.next() {
+            Some(_) => {
+                if let Some(i) = slash {
+                    return Err(unexpected(i));
+                }
+                parts.push(part);
+            }
+            None => {
+                parts.push(part);
+                return Ok(parts);
+            }
+        }
+    }
+}
+
+/// Parse a complete value, such as the right-hand side of a key-value pair,
+/// that starts at column `col` of line `line_num`.
+fn parse_value(
+    value_str: &str,
+    options: &ParserOptions,
+    line_num: usize,
+    col: usize,
+) -> Result<Value, ParseError> {
+    let mut parser = ValueParser::new(value_str, options, line_num, col);
+    let value = parser.value(Context::TopLevel)?;
+    parser.skip_whitespace();
+    if parser.pos < value_str.len() {
+        return Err(parser.invalid(
+            parser.pos,
+            &value_str[parser.pos..],
+            "Unexpected characters after value",
+        ));
+    }
+    Ok(value)
+}
+
+/// Where a value sits, which decides what ends an unquoted scalar.
+#[derive(Clone, Copy)]
+enum Context {
+    /// The whole remaining input belongs to the value.
+    TopLevel,
+    /// An element of an array, ended by `,` or `]`.
+    Array,
+    /// The value of an object pair, ended by `,`, `}` or another `==`.
+    Object,
+}
+
+impl Context {
+    fn delimiters(self) -> &'static [&'static str] {
+        match self {
+            Context::TopLevel => &[],
+            Context::Array => &[",", "]"],
+            Context::Object => &[",", "}", "=="],
+        }
+    }
+}
+
+/// Recursive-descent parser for a single value. Positions are byte offsets
+/// into `src`; they are only turned into columns when reporting errors.
+struct ValueParser<'a> {
+    src: &'a str,
+    pos: usize,
+    options: &'a ParserOptions,
+    line: usize,
+    col: usize,
+    /// The last `(offset, line, column)` computed, so positions are counted
+    /// incrementally instead of from the start each time.
+    last_location: Cell<(usize, usize, usize)>,
+    /// How many arrays and objects enclose the current position.
+    depth: usize,
+}
+
+impl<'a> ValueParser<'a> {
+    fn new(src: &'a str, options: &'a ParserOptions, line: usize, col: usize) -> Self {
+        Self {
+            src,
+            pos: 0,
+            options,
+            line,
+            col,
+            last_location: Cell::new((0, line, col)),
+            depth: 0,
+        }
+    }
+
+    fn value(&mut self, context: Context) -> Result<Value, ParseError> {
+        match self.peek() {
+            Some('[') => self.nested(Self::array),
+            Some('{') => self.nested(Self::object),
+            Some('"' | '\'') => self.string().map(Value::String),
+            Some('`') => Ok(self.raw(context)),
+            _ => self.scalar(context),
+        }
+    }
+
+    /// Parse a container with `parse`, one level deeper.
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Value, ParseError>,
+    ) -> Result<Value, ParseError> {
+        if self.depth == self.options.max_depth {
+            let (line, column) = self.location(self.pos);
+            return Err(ParseError::NestingTooDeep {
+                line,
+                column,
+                limit: self.options.max_depth,
+            });
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn array(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.eat(']') {
+            return Ok(Value::Array(items));
+        }
+        loop {
+            self.check_elements(start, items.len())?;
+            items.push(self.value(Context::Array)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    // A single trailing comma is allowed.
+                    if self.eat(']') {
+                        break;
+                    }
+                }
+                Some(']') => {
+                    self.pos += 1;
+                    break;
+                }
+                None => return Err(self.invalid(start, &self.src[start..], "Unterminated array")),
+                Some(_) => {
+                    let end = self.find_delimiter(self.pos, Context::Array.delimiters());
+                    let text = self.src[self.pos..end].trim_end();
+                    return Err(self.invalid(self.pos, text, "Unexpected characters after value"));
+                }
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn object(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut object = IndexMap::new();
+        self.skip_whitespace();
+        if self.eat('}') {
+            return Ok(Value::Object(object));
+        }
+        loop {
+            self.check_elements(start, object.len())?;
+            let pair_start = self.pos;
+            let key = self.object_key(pair_start)?;
+            if object.contains_key(&key) {
+                return Err(self.invalid(pair_start, &key, "Duplicate key in object"));
+            }
+            self.skip_whitespace();
+            if matches!(self.peek(), Some(',' | '}')) {
+                return Err(self.invalid_pair(pair_start, "Missing value in object pair"));
+            }
+            let value = self.value(Context::Object)?;
+            object.insert(key, value);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    // A single trailing comma is allowed.
+                    if self.eat('}') {
+                        break;
+                    }
+                }
+                Some('}') => {
+                    self.pos += 1;
+                    break;
+                }
+                None => return Err(self.invalid(start, &self.src[start..], "Unterminated object")),
+                Some(_) => {
+                    return Err(
+                        self.invalid_pair(pair_start, "Expected ',' or '}' after object pair")
+                    )
+                }
+            }
+        }
+        Ok(Value::Object(object))
+    }
+
+    /// Fail if the container starting at `start`, which already holds `len`
+    /// elements, may not hold another.
+    fn check_elements(&self, start: usize, len: usize) -> Result<(), ParseError> {
+        match self.options.max_elements {
+            Some(limit) if len == limit => {
+                let (line, column) = self.location(start);
+                Err(ParseError::TooManyElements {
+                    line,
+                    column,
+                    limit,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Parse an object key and the `==` after it.
+    fn object_key(&mut self, pair_start: usize) -> Result<String, ParseError> {
+        let key_start = self.pos;
+        let quoted = if matches!(self.peek(), Some('"' | '\'')) {
+            Some(self.string()?)
+        } else {
+            let end = self.find_delimiter(key_start, Context::Object.delimiters());
+            self.pos = key_start + self.src[key_start..end].trim_end().len();
+            None
+        };
+        let key_end = self.pos;
+        if quoted.is_none() && key_start == key_end {
+            return Err(self.invalid_pair(pair_start, "Missing key in object pair"));
+        }
+
+        self.skip_whitespace();
+        let rest = &self.src[self.pos..];
+        if !rest.starts_with("==") || rest.starts_with("===") {
+            return Err(self.invalid_pair(
+                pair_start,
+                "Expected '==' between key and value in object pair",
+            ));
+        }
+        self.pos += 2;
+
+        match quoted {
+            Some(key) => Ok(key),
+            None => {
+                let key = &self.src[key_start..key_end];
+                if is_bare_key(key) {
+                    Ok(key.to_string())
+                } else {
+                    Err(self.invalid(key_start, key, "Invalid object key"))
+                }
+            }
+        }
+    }
+
+    /// Parse a single-, double- or triple-quoted string.
+    fn string(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        let rest = &self.src[start..];
+        if let Some(body) = rest.strip_prefix(TRIPLE_QUOTE) {
+            let len = body.find(TRIPLE_QUOTE).ok_or_else(|| {
+                self.invalid(start, TRIPLE_QUOTE, "Unterminated triple-quoted string")
+            })?;
+            self.pos = start + 2 * TRIPLE_QUOTE.len() + len;
+            return Ok(dedent(&body[..len]));
+        }
+
+        let bytes = rest.as_bytes();
+        let quote = bytes[0];
+        let mut i = 1;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b if b == quote => {
+                    self.pos = start + i + 1;
+                    let (line, column) = self.location(start + 1);
+                    return unescape(&rest[1..i], line, column);
+                }
+                _ => i += 1,
+            }
+        }
+        Err(self.invalid(start, rest, "Unterminated string"))
+    }
+
+    /// Take the text after a backtick as a string without interpreting it;
+    /// see [`raw_len`] for where it ends.
+    fn raw(&mut self, context: Context) -> Value {
+        let start = self.pos + 1;
+        let end = start + raw_len(&self.src.as_bytes()[start..], context.delimiters());
+        let text = self.src[start..end].trim_end();
+        self.pos = start + text.len();
+        Value::String(text.to_string())
+    }
+
+    /// Parse an unquoted value, which runs until the end of the context.
+    fn scalar(&mut self, context: Context) -> Result<Value, ParseError> {
+        let start = self.pos;
+        let end = self.find_delimiter(start, context.delimiters());
+        let token = self.src[start..end].trim_end();
+        self.pos = start + token.len();
+        let (line, column) = self.location(start);
+        parse_scalar(token, self.options, line, column)
+    }
+
+    /// Byte offset of the next of `delimiters` at or after `from` that isn't
+    /// nested in brackets or quotes, or the end of the input. A comment ends
+    /// the search as well, unless it is nested.
+    fn find_delimiter(&self, from: usize, delimiters: &[&str]) -> usize {
+        let bytes = self.src.as_bytes();
+        let mut depth = 0usize;
+        let mut quote: Option<u8> = None;
+        let mut i = from;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' if quote.is_some() => i += 1,
+                b if quote == Some(b) => quote = None,
+                _ if quote.is_some() => {}
+                _ if starts_comment(&bytes[i..]) => {
+                    if depth == 0 {
+                        return i;
+                    }
+                    i += self.src[i..].find('\n').unwrap_or(bytes.len() - i);
+                }
+                _ if depth == 0
+                    && delimiters
+                        .iter()
+                        .any(|d| bytes[i..].starts_with(d.as_bytes())) =>
+                {
+                    return i
+                }
+                b @ (b'"' | b'\'') => quote = Some(b),
+                b'`' => {
+                    let ends: &[&str] = if depth > 0 {
+                        CONTAINER_DELIMITERS
+                    } else {
+                        delimiters
+                    };
+                    i += raw_len(&bytes[i + 1..], ends);
+                }
+                b'[' | b'{' => depth += 1,
+                b']' | b'}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            i += 1;
+        }
+        bytes.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let matched = self.peek() == Some(c);
+        if matched {
+            self.pos += c.len_utf8();
+        }
+        matched
+    }
+
+    /// Skip whitespace, including line breaks, and comments running to the
+    /// end of a line.
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = &self.src[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if !starts_comment(trimmed) {
+                break;
+            }
+            self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    /// Line and column of the byte at `offset`. Values only span lines when
+    /// they are multi-line arrays or objects, whose later lines start at
+    /// column 1.
+    fn location(&self, offset: usize) -> (usize, usize) {
+        let (mut from, mut line, mut col) = self.last_location.get();
+        if offset < from {
+            (from, line, col) = (0, self.line, self.col);
+        }
+        let segment = &self.src[from..offset];
+        match segment.rfind('\n') {
+            Some(newline) => {
+                line += segment.matches('\n').count();
+                col = 1 + segment[newline + 1..].chars().count();
+            }
+            None => col += segment.chars().count(),
+        }
+        self.last_location.set((offset, line, col));
+        (line, col)
+    }
+
+    fn invalid(&self, offset: usize, text: &str, reason: &str) -> ParseError {
+        let (line, column) = self.location(offset);
+        ParseError::InvalidValue {
+            line,
+            column,
+            text: text.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Error for an object pair starting at `pair_start` that isn't of the
+    /// form `key == value`.
+    fn invalid_pair(&self, pair_start: usize, reason: &str) -> ParseError {
+        let end = self.find_delimiter(pair_start, &[",", "}"]);
+        self.invalid(pair_start, self.src[pair_start..end].trim_end(), reason)
+    }
+}
+
+/// Parse an unquoted literal: a boolean, `Null`, a number, a duration, a
+/// byte size or, with the `chrono` feature, a timestamp.
+fn parse_scalar(
+    value_str: &str,
+    options: &ParserOptions,
+    line_num: usize,
+    col: usize,
+) -> Result<Value, ParseError> {
+    let invalid = |reason: &str| ParseError::InvalidValue {
+        line: line_num,
+        column: col,
+        text: value_str.to_string(),
+        reason: reason.to_string(),
+    };
+    let overflow = || ParseError::IntegerOverflow {
+        line: line_num,
+        column: col,
+        text: value_str.to_string(),
+    };
+    // `None` if an underscore doesn't sit between two digits.
+    let number_str = strip_digit_separators(value_str, |c| c.is_ascii_digit());
+
+    match value_str {
+        "True" => return Ok(Value::Boolean(true)),
+        "False" => return Ok(Value::Boolean(false)),
+        "Null" => return Ok(Value::Null),
+        _ => {}
+    }
+    if options.lenient_booleans && value_str.eq_ignore_ascii_case("null") {
+        return Ok(Value::Null);
+    } else if let Some(b) = lenient_bool(value_str).filter(|_| options.lenient_booleans) {
+        return Ok(Value::Boolean(b));
+    } else if let Some((sign, radix, name, digits)) = split_radix(value_str) {
+        let digits = strip_digit_separators(digits, |c| c.is_ascii_alphanumeric())
+            .ok_or_else(|| invalid("Misplaced digit separator in"))?;
+        return i64::from_str_radix(&format!("{}{}", sign, digits), radix)
+            .map(Value::Integer)
+            .map_err(|e| match e.kind() {
+                IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => overflow(),
+                _ => invalid(&format!("Invalid {} integer", name)),
+            });
+    } else if let Some(number) = number_str.as_deref().filter(|n| is_integer(n)) {
+        // `i64::from_str` accepts an optional leading `+` or `-`, so the only
+        // way this can fail is a value out of range for i64.
+        return number
+            .parse::<i64>()
+            .map(Value::Integer)
+            .map_err(|_| overflow());
+    } else if let Some(number) = number_str.as_deref().filter(|n| is_float(n)) {
+        // `is_float` only admits forms `f64::from_str` understands, so this can't fail.
+        return Ok(Value::Float(number.parse::<f64>().unwrap()));
+    } else if let Some(components) = duration_components(value_str) {
+        return parse_duration(&components)
+            .map(Value::Duration)
+            .ok_or_else(|| invalid("Duration out of range"));
+    } else if let Some((amount, unit)) = split_byte_size(value_str) {
+        return amount
+            .parse::<u64>()
+            .ok()
+            .and_then(|n| n.checked_mul(unit))
+            .map(Value::Bytes)
+            .ok_or_else(|| invalid("Byte size out of range"));
+    }
+    #[cfg(feature = "chrono")]
+    if let Ok(t) = chrono::DateTime::parse_from_rfc3339(value_str) {
+        return Ok(Value::DateTime(t.to_utc()));
+    }
+
+    let without_separators = value_str.replace('_', "");
+    if number_str.is_none() && (is_integer(&without_separators) || is_float(&without_separators)) {
+        return Err(invalid("Misplaced digit separator in"));
+    }
+    Err(invalid("Unable to parse value"))
+}
+
+/// Split an optional leading `+` or `-` from `s`.
+fn split_sign(s: &str) -> (&str, &str) {
+    match s.strip_prefix(['+', '-']) {
+        Some(rest) => (&s[..1], rest),
+        None => ("", s),
+    }
+}
+
+/// Whether `s` is an optionally signed run of ASCII digits.
+fn is_integer(s: &str) -> bool {
+    let (_, digits) = split_sign(s);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Whether `s` is an optionally signed decimal with an optional exponent, such
+/// as `1.5`, `.5`, `5.` or `2e10`, or `inf`, `infinity` or `nan` in any case.
+fn is_float(s: &str) -> bool {
+    let (_, s) = split_sign(s);
+    if ["inf", "infinity", "nan"]
+        .iter()
+        .any(|word| s.eq_ignore_ascii_case(word))
+    {
+        return true;
+    }
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (s, None),
+    };
+    let (whole, fraction) = match mantissa.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (mantissa, ""),
+    };
+    let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    all_digits(whole)
+        && all_digits(fraction)
+        && !(whole.is_empty() && fraction.is_empty())
+        && exponent.is_none_or(is_integer)
+}
+
+/// Split a `0x`, `0o` or `0b` integer into its sign, radix, radix name and
+/// digits. The digits aren't checked against the radix.
+fn split_radix(s: &str) -> Option<(&str, u32, &'static str, &str)> {
+    let (sign, rest) = split_sign(s);
+    let rest = rest.strip_prefix('0')?;
+    let (radix, name) = match rest.chars().next()? {
+        'x' => (16, "hexadecimal"),
+        'o' => (8, "octal"),
+        'b' => (2, "binary"),
+        _ => return None,
+    };
+    let digits = &rest[1..];
+    digits
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_')
+        .then_some((sign, radix, name, digits))
+}
+
+/// Split a duration such as `1h30m` into its `(amount, unit)` components.
+/// Returns `None` if `s` isn't a duration. Each unit may appear once, after
+/// any larger ones, so `1h1h` and `30s1h` aren't durations.
+fn duration_components(s: &str) -> Option<Vec<(&str, &str)>> {
+    // Each unit with its rank by size, largest first; `ms` must be tried
+    // before `m`.
+    const UNITS: [(&str, usize); 7] = [
+        ("d", 0),
+        ("h", 1),
+        ("ms", 4),
+        ("m", 2),
+        ("s", 3),
+        ("us", 5),
+        ("ns", 6),
+    ];
+    let mut components = Vec::new();
+    let mut rest = s;
+    let mut last_rank = None;
+    while !rest.is_empty() {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            return None;
+        }
+        let (amount, tail) = rest.split_at(digits);
+        let (unit, rank) = UNITS.into_iter().find(|(unit, _)| tail.starts_with(unit))?;
+        if last_rank >= Some(rank) {
+            return None;
+        }
+        last_rank = Some(rank);
+        components.push((amount, unit));
+        rest = &tail[unit.len()..];
+    }
+    (!components.is_empty()).then_some(components)
+}
+
+/// Sum the components of a duration. Returns `None` if the total doesn't fit
+/// in a `Duration`.
+fn parse_duration(components: &[(&str, &str)]) -> Option<Duration> {
+    let mut total = Duration::ZERO;
+    for (amount, unit) in components {
+        let n: u64 = amount.parse().ok()?;
+        let component = match *unit {
+            "ns" => Duration::from_nanos(n),
+            "us" => Duration::from_micros(n),
+            "ms" => Duration::from_millis(n),
+            "s" => Duration::from_secs(n),
+            "m" => Duration::from_secs(n.checked_mul(60)?),
+            "h" => Duration::from_secs(n.checked_mul(60 * 60)?),
+            "d" => Duration::from_secs(n.checked_mul(24 * 60 * 60)?),
+            _ => unreachable!(),
+        };
+        total = total.checked_add(component)?;
+    }
+    Some(total)
+}
+
+/// Split a byte size such as `10MB` into its amount and the size of its unit.
+fn split_byte_size(s: &str) -> Option<(&str, u64)> {
+    let digits = s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    let (amount, unit) = s.split_at(digits);
+    Some((amount, byte_unit(unit)?))
+}
+
+/// Number of bytes in a size unit. SI units are powers of 1000, binary units
+/// (`KiB` etc.) powers of 1024.
+pub(crate) fn byte_unit(unit: &str) -> Option<u64> {
+    match unit {
+        "B" => Some(1),
+        "KB" => Some(1_000),
+        "MB" => Some(1_000_000),
+        "GB" => Some(1_000_000_000),
+        "KiB" => Some(1 << 10),
+        "MiB" => Some(1 << 20),
+        "GiB" => Some(1 << 30),
+        _ => None,
+    }
+}
+
+/// Remove underscores used as digit separators, as in `1_000_000`.
+///
+/// Returns `None` if an underscore is not surrounded by characters matching `is_digit`.
+fn strip_digit_separators(s: &str, is_digit: fn(char) -> bool) -> Option<Cow<'_, str>> {
+    if !s.contains('_') {
+        return Some(Cow::Borrowed(s));
+    }
+    let chars: Vec<char> = s.chars().collect();
+    for (i, c) in chars.iter().enumerate() {
+        if *c == '_' {
+            let before = i.checked_sub(1).map(|j| chars[j]);
+            let after = chars.get(i + 1).copied();
+            if !before.is_some_and(is_digit) || !after.is_some_and(is_digit) {
+                return None;
+            }
+        }
+    }
+    Some(Cow::Owned(s.replace('_', "")))
+}
+
+/// Substitute environment variables into every string inside `value`.
+fn interpolate_env(value: &mut Value, line_num: usize, col: usize) -> Result<(), ParseError> {
+    match value {
+        Value::String(s) => {
+            let mut result = String::with_capacity(s.len());
+            let mut last = 0;
+            for caps in VAR_REGEX.captures_iter(s) {
+                let whole = caps.get(0).unwrap();
+                let name = caps.get(1).unwrap().as_str();
+                let replacement = match (env::var(name), caps.get(2)) {
+                    (Ok(var), _) => var,
+                    (Err(_), Some(fallback)) => fallback.as_str().to_string(),
+                    (Err(_), None) => {
+                        return Err(ParseError::UndefinedVariable {
+                            line: line_num,
+                            column: col,
+                            name: name.to_string(),
+                        })
+                    }
+                };
+                result.push_str(&s[last..whole.start()]);
+                result.push_str(&replacement);
+                last = whole.end();
+            }
+            result.push_str(&s[last..]);
+            *s = result;
+        }
+        Value::Array(items) => {
+            for item in items {
+                interpolate_env(item, line_num, col)?;
+            }
+        }
+        Value::Object(object) => {
+            for item in object.values_mut() {
+                interpolate_env(item, line_num, col)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Cut a trailing `#`, `//` or `;` comment from `line`, ignoring comment markers
+/// inside quoted strings and those in raw values not preceded by whitespace.
+fn strip_inline_comment(line: &str) -> &str {
+    strip_inline_comment_within(line, 0)
+}
+
+/// Like [`strip_inline_comment`], for a line that continues `open` arrays and
+/// objects from earlier lines.
+fn strip_inline_comment_within(line: &str, open: usize) -> &str {
+    let bytes = line.as_bytes();
+    let mut quote: Option<u8> = None;
+    let mut depth = open;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if quote.is_some() => i += 1,
+            b if quote == Some(b) => quote = None,
+            _ if quote.is_some() => {}
+            b @ (b'"' | b'\'') => quote = Some(b),
+            b'`' => i += raw_len(&bytes[i + 1..], raw_ends(depth)),
+            b'[' | b'{' => depth += 1,
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            b'#' | b';' => return line[..i].trim_end(),
+            b'/' if bytes.get(i + 1) == Some(&b'/') => return line[..i].trim_end(),
+            _ => {}
+        }
+        i += 1;
+    }
+    line
+}
+
+/// Blanks out `/* ... */` comments, which may span lines, in the lines of a
+/// configuration. Commented characters become spaces, so columns are kept.
+struct BlockComments<I> {
+    lines: I,
+    line_num: usize,
+    /// Where the comment being skipped started, if inside one.
+    open_comment: Option<(usize, usize)>,
+    /// Whether a triple-quoted string continues onto the next line.
+    in_triple_quote: bool,
+    /// How many arrays and objects are open, which decides where raw values
+    /// end.
+    depth: usize,
+}
+
+impl<I> BlockComments<I> {
+    fn new(lines: I) -> Self {
+        Self {
+            lines,
+            line_num: 0,
+            open_comment: None,
+            in_triple_quote: false,
+            depth: 0,
+        }
+    }
+
+    fn strip(&mut self, line: &str) -> String {
+        let mut out = String::with_capacity(line.len());
+        let mut rest = line;
+        // Quotes and brackets in a section header are part of its names.
+        let header = self.open_comment.is_none()
+            && !self.in_triple_quote
+            && line.trim_start().starts_with('<');
+        while !rest.is_empty() {
+            let mut chars = rest.chars();
+            if self.open_comment.is_some() {
+                match rest.find("*/") {
+                    Some(end) => {
+                        out.extend(rest[..end + 2].chars().map(|_| ' '));
+                        rest = &rest[end + 2..];
+                        self.open_comment = None;
+                    }
+                    None => {
+                        out.extend(rest.chars().map(|_| ' '));
+                        rest = "";
+                    }
+                }
+            } else if header && rest.starts_with('\\') && rest[1..].starts_with(is_header_escape) {
+                let len = 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+                out.push_str(&rest[..len]);
+                rest = &rest[len..];
+            } else if !header && (self.in_triple_quote || rest.starts_with(TRIPLE_QUOTE)) {
+                let from = if self.in_triple_quote {
+                    0
+                } else {
+                    TRIPLE_QUOTE.len()
+                };
+                let end = rest[from..]
+                    .find(TRIPLE_QUOTE)
+                    .map(|i| from + i + TRIPLE_QUOTE.len());
+                self.in_triple_quote = end.is_none();
+                let end = end.unwrap_or(rest.len());
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+            } else if rest.starts_with("/*") {
+                let column = line[..line.len() - rest.len()].chars().count() + 1;
+                self.open_comment = Some((self.line_num, column));
+                out.push_str("  ");
+                rest = &rest[2..];
+            } else if starts_comment(rest) {
+                out.push_str(rest);
+                rest = "";
+            } else if let Some(quote @ ('"' | '\'')) = chars.next().filter(|_| !header) {
+                let mut end = rest.len();
+                let mut escaped = false;
+                for (i, c) in chars.as_str().char_indices() {
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        c if c == quote => {
+                            end = i + 2;
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+            } else if !header && rest.starts_with('`') {
+                let end = 1 + raw_len(&rest.as_bytes()[1..], raw_ends(self.depth));
+                out.push_str(&rest[..end]);
+                rest = &rest[end..];
+            } else {
+                match rest.as_bytes()[0] {
+                    b'[' | b'{' if !header => self.depth += 1,
+                    b']' | b'}' if !header => self.depth = self.depth.saturating_sub(1),
+                    _ => {}
+                }
+                let len = rest.chars().next().map_or(1, char::len_utf8);
+                out.push_str(&rest[..len]);
+                rest = &rest[len..];
+            }
+        }
+        out
+    }
+}
+
+impl<L: AsRef<str>, I: Iterator<Item = Result<L, ParseError>>> Iterator for BlockComments<I> {
+    type Item = Result<String, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.lines.next() {
+            Some(line) => {
+                self.line_num += 1;
+                Some(line.map(|line| self.strip(line.as_ref())))
+            }
+            None => self
+                .open_comment
+                .take()
+                .map(|(line, column)| Err(ParseError::UnterminatedBlockComment { line, column })),
+        }
+    }
+}
+
+/// What ends an element of an array or object.
+const CONTAINER_DELIMITERS: &[&str] = &[",", "]", "}"];
+
+/// What ends a raw value at bracket `depth` of a line: nothing at the top
+/// level, and the end of the element inside an array or object.
+fn raw_ends(depth: usize) -> &'static [&'static str] {
+    if depth > 0 {
+        CONTAINER_DELIMITERS
+    } else {
+        &[]
+    }
+}
+
+/// The length of the raw value at the start of `s`, just after its backtick.
+///
+/// Quotes and brackets in raw values are ordinary characters, and a comment
+/// marker only starts a comment after whitespace, so `` `don't;stop `` is the
+/// string `don't;stop` but `` `a # b `` is `a`. A raw value also ends at the
+/// end of the line and at the first of `delimiters`.
+fn raw_len(s: &[u8], delimiters: &[&str]) -> usize {
+    let mut i = 0;
+    while i < s.len() {
+        let rest = &s[i..];
+        let comment_follows = || starts_comment(&rest[1..]) || rest[1..].starts_with(b"/*");
+        if matches!(rest[0], b'\n' | b'\r')
+            || delimiters.iter().any(|d| rest.starts_with(d.as_bytes()))
+            || (rest[0].is_ascii_whitespace() && comment_follows())
+        {
+            break;
+        }
+        i += 1;
+    }
+    i
+}
+
+/// Whether `s` starts with a `#`, `//` or `;` comment. Taking bytes lets
+/// scanners ask at any offset, even inside a multi-byte character.
+fn starts_comment(s: impl AsRef<[u8]>) -> bool {
+    let s = s.as_ref();
+    matches!(s.first(), Some(b'#' | b';')) || s.starts_with(b"//")
+}
+
+/// How many more arrays and objects `line` opens than it closes, ignoring
+/// brackets in strings and comments, when `open` are already open before it.
+fn bracket_depth(line: &str, open: usize) -> isize {
+    let bytes = strip_inline_comment_within(line, open).as_bytes();
+    let mut quote: Option<u8> = None;
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if quote.is_some() => i += 1,
+            b if quote == Some(b) => quote = None,
+            _ if quote.is_some() => {}
+            b @ (b'"' | b'\'') => quote = Some(b),
+            b'`' => {
+                let depth = (open as isize + depth).max(0) as usize;
+                i += raw_len(&bytes[i + 1..], raw_ends(depth));
+            }
+            b'[' | b'{' => depth += 1,
+            b']' | b'}' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    depth
+}
+
+/// Collect an array or object starting at `first` (the rest of line
+/// `line_num`) that isn't closed on that line, pulling further lines from
+/// `lines` until its brackets balance. Returns the lines joined by `\n`,
+/// comments included.
+fn read_container<L: AsRef<str>>(
+    first: &str,
+    lines: &mut impl Iterator<Item = (usize, Result<L, ParseError>)>,
+    line_num: usize,
+    col: usize,
+) -> Result<String, ParseError> {
+    let mut text = first.to_string();
+    let mut depth = bracket_depth(first, 0);
+    while depth > 0 {
+        let Some((_, next)) = lines.next() else {
+            let reason = if first.starts_with('[') {
+                "Unterminated array"
+            } else {
+                "Unterminated object"
+            };
+            return Err(ParseError::InvalidValue {
+                line: line_num,
+                column: col,
+                text: strip_inline_comment(first).to_string(),
+                reason: reason.to_string(),
+            });
+        };
+        let next = next?;
+        let next = next.as_ref();
+        depth += bracket_depth(next, depth as usize);
+        text.push('\n');
+        text.push_str(next);
+    }
+    Ok(text)
+}
+
+/// Column of the byte at `offset` in `s`, given that `s` starts at column `col`.
+fn column_at(s: &str, col: usize, offset: usize) -> usize {
+    col + s[..offset].chars().count()
+}
+
+/// Collect a triple-quoted string starting at `first` (the rest of line
+/// `line_num`), pulling further lines from `lines` until the closing quotes.
+/// Returns the string including its delimiters.
+fn read_triple_quoted<L: AsRef<str>>(
+    first: &str,
+    lines: &mut impl Iterator<Item = (usize, Result<L, ParseError>)>,
+    line_num: usize,
+    col: usize,
+) -> Result<String, ParseError> {
+    // Anything after the closing quotes on `line` may only be a comment.
+    let check_rest = |line: &str, end: usize, line_num: usize| {
+        let rest = line[end..].trim();
+        if strip_inline_comment(rest).is_empty() {
+            Ok(())
+        } else {
+            Err(ParseError::UnexpectedToken {
+                line: line_num,
+                column: column_at(line, 1, line.len() - rest.len()),
+                text: rest.to_string(),
+            })
+        }
+    };
+
+    let search_from = TRIPLE_QUOTE.len();
+    if let Some(len) = first[search_from..].find(TRIPLE_QUOTE) {
+        let end = search_from + len + TRIPLE_QUOTE.len();
+        check_rest(first, end, line_num)?;
+        return Ok(first[..end].to_string());
+    }
+
+    let mut text = format!("{}\n", first);
+    loop {
+        let (next_num, next) = lines.next().ok_or_else(|| ParseError::InvalidValue {
+            line: line_num,
+            column: col,
+            text: TRIPLE_QUOTE.to_string(),
+            reason: "Unterminated triple-quoted string".to_string(),
+        })?;
+        let next = next?;
+        let next = next.as_ref();
+        if let Some(len) = next.find(TRIPLE_QUOTE) {
+            let end = len + TRIPLE_QUOTE.len();
+            check_rest(next, end, next_num + 1)?;
+            text.push_str(&next[..end]);
+            return Ok(text);
+        }
+        text.push_str(next);
+        text.push('\n');
+    }
+}
+
+/// Read a boolean in the forms accepted by [`ParserOptions::lenient_booleans`].
+fn lenient_bool(s: &str) -> Option<bool> {
+    match s.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" => Some(true),
+        "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Tidy the contents of a multi-line triple-quoted string: drop the line break
+/// right after the opening quotes, empty a whitespace-only last line (the one
+/// holding the closing quotes) and strip the indentation shared by all
+/// non-blank lines. Single-line contents are returned verbatim.
+fn dedent(raw: &str) -> String {
+    let raw = raw.strip_prefix('\n').unwrap_or(raw);
+    let mut lines: Vec<&str> = raw.split('\n').collect();
+    if lines.len() == 1 {
+        return raw.to_string();
+    }
+    if let Some(last) = lines.last_mut() {
+        if last.trim().is_empty() {
+            *last = "";
+        }
+    }
+
+    let indent_of = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indent_of(line))
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                ""
+            } else {
+                &line[indent..]
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Return the text between matching single or double quotes, if `s` is quoted.
+fn quoted_contents(s: &str) -> Option<&str> {
+    let quote = s.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    if s.len() >= 2 && s.ends_with(quote) {
+        Some(&s[1..s.len() - 1])
+    } else {
+        None
+    }
+}
+
+/// Resolve backslash escape sequences in the contents of a quoted string that
+/// starts at column `col`.
+fn unescape(raw: &str, line_num: usize, col: usize) -> Result<String, ParseError> {
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.chars().enumerate();
+    while let Some((i, c)) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next().map(|(_, c)| c) {
+            Some('"') => result.push('"'),
+            Some('\'') => result.push('\''),
+            Some('\\') => result.push('\\'),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(other) => {
+                return Err(ParseError::InvalidValue {
+                    line: line_num,
+                    column: col + i,
+                    text: format!("\\{}", other),
+                    reason: "Unknown escape sequence".to_string(),
+                })
+            }
+            None => {
+                return Err(ParseError::InvalidValue {
+                    line: line_num,
+                    column: col + i,
+                    text: raw.to_string(),
+                    reason: "Unterminated escape sequence in".to_string(),
+                })
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formatter::is_writable_section;
+    use proptest::prelude::*;
 
     #[test]
     fn test_basic_config() {
@@ -180,6 +2523,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_config_equality() {
+        let input = std::fs::read_to_string("example.thtc").unwrap();
+        let first = ThethaCoreConfig::parse(&input).unwrap();
+        let second = ThethaCoreConfig::parse(&input).unwrap();
+        assert_eq!(first, second);
+
+        let reordered = ThethaCoreConfig::parse("<b>\ny == 2\n<a>\nx == 1").unwrap();
+        assert_eq!(
+            reordered,
+            ThethaCoreConfig::parse("<a>\nx == 1\n<b>\ny == 2").unwrap()
+        );
+        assert_ne!(
+            reordered,
+            ThethaCoreConfig::parse("<a>\nx == 1\n<b>\ny == 3").unwrap()
+        );
+        assert_ne!(reordered, ThethaCoreConfig::new());
+    }
+
     #[test]
     fn test_nested_sections() {
         let input = r#"
@@ -229,4 +2591,2606 @@ mod tests {
             panic!("Failed to parse object");
         }
     }
+
+    #[test]
+    fn test_nested_array_parsing() {
+        let input = r#"
+        <data>
+        matrix == [[1,2],[3,4]]
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.sections.get("data").unwrap().get("matrix"),
+            Some(&Value::Array(vec![
+                Value::Array(vec![Value::Integer(1), Value::Integer(2)]),
+                Value::Array(vec![Value::Integer(3), Value::Integer(4)]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_object_with_nested_array() {
+        let input = r#"
+        <data>
+        obj == { a == [1,2], b == 3 }
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        if let Some(Value::Object(obj)) = config.sections.get("data").unwrap().get("obj") {
+            assert_eq!(obj.len(), 2);
+            assert_eq!(
+                obj.get("a"),
+                Some(&Value::Array(vec![Value::Integer(1), Value::Integer(2)]))
+            );
+            assert_eq!(obj.get("b"), Some(&Value::Integer(3)));
+        } else {
+            panic!("Failed to parse object");
+        }
+    }
+
+    #[test]
+    fn test_array_with_quoted_commas() {
+        let input = r#"
+        <data>
+        tags == ["a,b", "c"]
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.sections.get("data").unwrap().get("tags"),
+            Some(&Value::Array(vec![
+                Value::String("a,b".to_string()),
+                Value::String("c".to_string()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_object_with_quoted_separators() {
+        let input = r#"
+        <data>
+        obj == { "a==b" == "x, y", c == "[" }
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        if let Some(Value::Object(obj)) = config.sections.get("data").unwrap().get("obj") {
+            assert_eq!(obj.len(), 2);
+            assert_eq!(obj.get("a==b"), Some(&Value::String("x, y".to_string())));
+            assert_eq!(obj.get("c"), Some(&Value::String("[".to_string())));
+        } else {
+            panic!("Failed to parse object");
+        }
+    }
+
+    #[test]
+    fn test_large_array() {
+        let items: Vec<String> = (0..10_000).map(|i| i.to_string()).collect();
+        let input = format!("<data>\nitems == [{}]", items.join(", "));
+        let config = ThethaCoreConfig::parse(&input).unwrap();
+        let items = config.get_value("data", "items").and_then(Value::as_array);
+        assert_eq!(items.map(<[Value]>::len), Some(10_000));
+        assert_eq!(items.unwrap()[9_999], Value::Integer(9_999));
+    }
+
+    #[test]
+    fn test_duplicate_object_keys() {
+        let err = ThethaCoreConfig::parse("<data>\nobj == { a == 1, a == 2 }\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 18: Duplicate key in object 'a'"
+        );
+
+        let err = ThethaCoreConfig::parse("<data>\nobj == { x == { \"k\" == 1, 'k' == 2 } }\n")
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InvalidValue { line: 2, ref text, .. } if text == "k"
+        ));
+
+        let config =
+            ThethaCoreConfig::parse("<data>\nobj == { a == { a == 1 }, b == { a == 2 } }\n");
+        assert!(config.is_ok());
+    }
+
+    #[test]
+    fn test_whitespace_only_containers() {
+        let input = "<data>\nitems == [   ]\ncfg == {  }\ntabs == [\t]\nnested == [ [ ] , {\t} ]\n";
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let data = config.section("data").unwrap();
+        assert_eq!(data.get("items"), Some(&Value::Array(vec![])));
+        assert_eq!(data.get("cfg"), Some(&Value::Object(IndexMap::new())));
+        assert_eq!(data.get("tabs"), Some(&Value::Array(vec![])));
+        assert_eq!(
+            data.get("nested"),
+            Some(&Value::Array(vec![
+                Value::Array(vec![]),
+                Value::Object(IndexMap::new()),
+            ]))
+        );
+
+        for value in ["[ , ]", "{ , }"] {
+            let input = format!("<data>\nx == {}\n", value);
+            assert!(
+                ThethaCoreConfig::parse(&input).is_err(),
+                "{} should not parse",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_nesting_depth_limit() {
+        let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        let err = ThethaCoreConfig::parse(&format!("<data>\nx == {}\n", deep)).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::NestingTooDeep {
+                line: 2,
+                column: 134,
+                limit: 128
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 134: Nesting too deep (the limit is 128 levels)"
+        );
+
+        let objects = format!("{}1{}", "{ a == ".repeat(3), " }".repeat(3));
+        let input = format!("<data>\nx == {}\n", objects);
+        let options = ParserOptions {
+            max_depth: 2,
+            ..ParserOptions::default()
+        };
+        assert!(ThethaCoreConfig::parse_with_options(&input, &options).is_err());
+        let options = ParserOptions {
+            max_depth: 3,
+            ..ParserOptions::default()
+        };
+        assert!(ThethaCoreConfig::parse_with_options(&input, &options).is_ok());
+    }
+
+    #[test]
+    fn test_parser_options_builder() {
+        let options = ParserOptions::new()
+            .strict_duplicates(true)
+            .lenient_booleans(true)
+            .max_lines(5);
+        assert!(options.strict_duplicates);
+        assert!(options.lenient_booleans);
+        assert!(!options.interpolate_env);
+        assert_eq!(options.max_lines, Some(5));
+        assert_eq!(options.max_depth, DEFAULT_MAX_DEPTH);
+
+        let input = "<flags>\non == yes\n";
+        let config = ThethaCoreConfig::parse_with_options(input, &options).unwrap();
+        assert_eq!(config.get_bool("flags", "on"), Some(true));
+        let err =
+            ThethaCoreConfig::parse_with_options("<f>\na == 1\na == 2", &options).unwrap_err();
+        assert!(matches!(err, ParseError::DuplicateKey { line: 3, .. }));
+        assert!(ThethaCoreConfig::parse_with_options(input, &ParserOptions::new()).is_err());
+
+        let options = options.lenient_booleans(false).max_depth(1);
+        assert!(ThethaCoreConfig::parse_with_options(input, &options).is_err());
+        assert!(ThethaCoreConfig::parse_with_options("<d>\nx == [[1]]", &options).is_err());
+        assert!(ThethaCoreConfig::parse_with_options("<d>\nx == [1]", &options).is_ok());
+    }
+
+    #[test]
+    fn test_input_limits() {
+        let parse = |input: &str, options: ParserOptions| {
+            ThethaCoreConfig::parse_with_options(input, &options)
+        };
+        let input = "<data>\na == [1, 2, 3]\nb == { \"x\" == 1, \"y\" == 2 }\n";
+
+        let err = parse(
+            input,
+            ParserOptions {
+                max_input_len: Some(20),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InputTooLarge { line: 3, limit: 20 }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Error: Input is longer than 20 bytes (line 3)"
+        );
+        assert!(parse(
+            input,
+            ParserOptions {
+                max_input_len: Some(47),
+                ..Default::default()
+            }
+        )
+        .is_ok());
+
+        let err = parse(
+            input,
+            ParserOptions {
+                max_lines: Some(2),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ParseError::TooManyLines { limit: 2 }));
+        assert_eq!(err.to_string(), "❌ Error: Input has more than 2 lines");
+        assert!(parse(
+            input,
+            ParserOptions {
+                max_lines: Some(3),
+                ..Default::default()
+            }
+        )
+        .is_ok());
+
+        let err = parse(
+            input,
+            ParserOptions {
+                max_elements: Some(2),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::TooManyElements {
+                line: 2,
+                column: 6,
+                limit: 2
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 6: Too many elements (the limit is 2)"
+        );
+        let err = parse(
+            "<d>\nb == { a == 1, b == 2 }",
+            ParserOptions {
+                max_elements: Some(1),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::TooManyElements {
+                column: 6,
+                limit: 1,
+                ..
+            }
+        ));
+        assert!(parse(
+            input,
+            ParserOptions {
+                max_elements: Some(3),
+                ..Default::default()
+            }
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_trailing_commas() {
+        let input = r#"
+        <data>
+        items == [1, 2, 3,]
+        nested == [[1,], { "a" == 1, },]
+        obj == { "a" == 1, "b" == [2,], }
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let data = config.sections.get("data").unwrap();
+        assert_eq!(
+            data.get("items"),
+            Some(&Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(2),
+                Value::Integer(3),
+            ]))
+        );
+        assert_eq!(
+            data.get("nested"),
+            Some(&Value::Array(vec![
+                Value::Array(vec![Value::Integer(1)]),
+                Value::Object(IndexMap::from([("a".to_string(), Value::Integer(1))])),
+            ]))
+        );
+        assert_eq!(
+            data.get("obj"),
+            Some(&Value::Object(IndexMap::from([
+                ("a".to_string(), Value::Integer(1)),
+                ("b".to_string(), Value::Array(vec![Value::Integer(2)])),
+            ])))
+        );
+    }
+
+    #[test]
+    fn test_empty_items_still_rejected() {
+        for value in ["[,]", "[1,,2]", "[1,,]", "{,}", "{ \"a\" == 1,, }"] {
+            let input = format!("<data>\nitems == {}", value);
+            assert!(
+                ThethaCoreConfig::parse(&input).is_err(),
+                "{} should not parse",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let input = r#"
+        <data>
+        msg == "line1\nline2"
+        quoted == "say \"hi\", \\o/\t\r"
+        list == ["a\"b", "c"]
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let data = config.sections.get("data").unwrap();
+        assert_eq!(
+            data.get("msg"),
+            Some(&Value::String("line1\nline2".to_string()))
+        );
+        assert_eq!(
+            data.get("quoted"),
+            Some(&Value::String("say \"hi\", \\o/\t\r".to_string()))
+        );
+        assert_eq!(
+            data.get("list"),
+            Some(&Value::Array(vec![
+                Value::String("a\"b".to_string()),
+                Value::String("c".to_string()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_unknown_escape_error() {
+        let input = "<data>\nmsg == \"bad\\x\"\n";
+        let err = ThethaCoreConfig::parse(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 12: Unknown escape sequence '\\x'"
+        );
+    }
+
+    #[test]
+    fn test_single_quoted_strings() {
+        let input = r#"
+        <data>
+        name == 'hello'
+        quote == 'say "hi"'
+        other == "it's"
+        list == ['a,b', "c"]
+        obj == { 'key' == 'x, y' }
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let data = config.sections.get("data").unwrap();
+        assert_eq!(data.get("name"), Some(&Value::String("hello".to_string())));
+        assert_eq!(
+            data.get("quote"),
+            Some(&Value::String("say \"hi\"".to_string()))
+        );
+        assert_eq!(data.get("other"), Some(&Value::String("it's".to_string())));
+        assert_eq!(
+            data.get("list"),
+            Some(&Value::Array(vec![
+                Value::String("a,b".to_string()),
+                Value::String("c".to_string()),
+            ]))
+        );
+        if let Some(Value::Object(obj)) = data.get("obj") {
+            assert_eq!(obj.get("key"), Some(&Value::String("x, y".to_string())));
+        } else {
+            panic!("Failed to parse object");
+        }
+    }
+
+    #[test]
+    fn test_structured_errors() {
+        let err = ThethaCoreConfig::parse("key == 1").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::ValueOutsideSection { line: 1, .. }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Error on line 1, col 1: Key-value pair found outside of a section"
+        );
+
+        let err = ThethaCoreConfig::parse("<data>\nnot a pair").unwrap_err();
+        assert!(
+            matches!(err, ParseError::UnexpectedToken { line: 2, ref text, .. } if text == "not a pair")
+        );
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 1: 'not a pair'"
+        );
+
+        let err = ThethaCoreConfig::parse("<data\n").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnterminatedSectionHeader { line: 1, .. }
+        ));
+
+        let err = ThethaCoreConfig::parse("<data>\nkey == @oops").unwrap_err();
+        assert!(
+            matches!(err, ParseError::InvalidValue { line: 2, ref text, .. } if text == "@oops")
+        );
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 8: Unable to parse value '@oops'"
+        );
+    }
+
+    #[test]
+    fn test_parse_with_spans() {
+        let input = "<general>\napp_name == \"TestApp\"\n\n<database<advanced>>\n  pool_size == 10\n  pool_size == 20\n\n<empty>\n";
+        let sections = ThethaCoreConfig::parse_with_spans(input).unwrap();
+        assert_eq!(
+            sections["general"]["app_name"],
+            Spanned {
+                value: Value::String("TestApp".to_string()),
+                line: 2,
+                column: 13,
+            }
+        );
+        let pool_size = &sections["database/advanced"]["pool_size"];
+        assert_eq!(pool_size.value, Value::Integer(20));
+        assert_eq!((pool_size.line, pool_size.column), (6, 16));
+        assert!(sections["empty"].is_empty());
+
+        let err = ThethaCoreConfig::parse_with_spans("<a>\nx == @").unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { line: 2, .. }));
+    }
+
+    #[test]
+    fn test_io_error() {
+        let err = ThethaCoreConfig::parse_from_file("does-not-exist.thtc").unwrap_err();
+        assert!(matches!(err, ParseError::Io { .. }));
+        assert!(std::error::Error::source(&err).is_some());
+        assert_eq!(
+            err.to_string(),
+            "❌ Error: Could not read file 'does-not-exist.thtc'"
+        );
+    }
+
+    #[test]
+    fn test_parse_from_reader() {
+        let file = File::open("example.thtc").unwrap();
+        let config = ThethaCoreConfig::parse_from_reader(file).unwrap();
+        let expected = ThethaCoreConfig::parse_from_file("example.thtc").unwrap();
+        assert_eq!(config.sections, expected.sections);
+
+        let input = "<db>\nquery == \"\"\"\n  SELECT 1\n  \"\"\"\nport == 5432\n";
+        let config = ThethaCoreConfig::parse_from_reader(input.as_bytes()).unwrap();
+        assert_eq!(config.get_string("db", "query"), Some("SELECT 1\n"));
+        assert_eq!(config.get_i64("db", "port"), Some(5432));
+    }
+
+    #[test]
+    fn test_parse_from_reader_errors() {
+        let err = ThethaCoreConfig::parse_from_reader(&b"<a>\nx == \xff\n"[..]).unwrap_err();
+        assert!(matches!(err, ParseError::Io { ref path, .. } if path == "<reader>"));
+
+        let err = ThethaCoreConfig::parse_from_reader("<a>\nx == @\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { line: 2, .. }));
+
+        let err =
+            ThethaCoreConfig::parse_from_reader("@include \"a.thtc\"".as_bytes()).unwrap_err();
+        assert!(matches!(err, ParseError::InvalidInclude { .. }));
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        let input = std::fs::read("example.thtc").unwrap();
+        let config = ThethaCoreConfig::parse_bytes(&input).unwrap();
+        let expected = ThethaCoreConfig::parse_from_file("example.thtc").unwrap();
+        assert_eq!(config.sections, expected.sections);
+
+        let err = ThethaCoreConfig::parse_bytes(b"<a>\nname == \"caf\xc3\x28\"\n").unwrap_err();
+        assert!(matches!(err, ParseError::InvalidUtf8 { offset: 16 }));
+        assert_eq!(err.to_string(), "❌ Error: Invalid UTF-8 at byte offset 16");
+
+        let err = ThethaCoreConfig::parse_bytes(b"<a>\nx == @\n").unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { line: 2, .. }));
+    }
+
+    #[test]
+    fn test_single_equals_hint() {
+        let err = ThethaCoreConfig::parse("<server>\n  port = 8080\n").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::SingleEquals {
+                line: 2,
+                column: 8,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 8: Found '=' in 'port = 8080'; use '==' to assign a value"
+        );
+
+        for line in ["port=8080", "\"max body\" = 1", "name =", "a.b-c = \"x\""] {
+            let input = format!("<server>\n{}\n", line);
+            let err = ThethaCoreConfig::parse(&input).unwrap_err();
+            assert!(
+                matches!(err, ParseError::SingleEquals { .. }),
+                "{}: got {}",
+                line,
+                err
+            );
+        }
+
+        let err = ThethaCoreConfig::parse("<server>\nport === 8080\n").unwrap_err();
+        assert!(!matches!(err, ParseError::SingleEquals { .. }));
+    }
+
+    #[test]
+    fn test_leading_bom() {
+        let input = "\u{feff}<general>\nname == \"x\"\n";
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.get_string("general", "name"), Some("x"));
+
+        let config = ThethaCoreConfig::parse_bytes(input.as_bytes()).unwrap();
+        assert!(config.contains_section("general"));
+
+        let dir = temp_dir("bom");
+        let path = dir.join("bom.thtc");
+        fs::write(&path, input).unwrap();
+        let config = ThethaCoreConfig::parse_from_file(path.to_str().unwrap()).unwrap();
+        assert!(config.contains_section("general"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let err = ThethaCoreConfig::parse("\u{feff}port = 1").unwrap_err();
+        assert!(matches!(err, ParseError::SingleEquals { column: 6, .. }));
+        assert!(ThethaCoreConfig::parse("<a>\n\u{feff}<b>").is_err());
+    }
+
+    #[test]
+    fn test_section_close_markers() {
+        let input = r#"
+        <database>
+        host == "a"
+        </database>
+        <database<replica>>
+        host == "b"
+        </database/replica> # done
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.get_string("database/replica", "host"), Some("b"));
+        let options = ParserOptions {
+            strict_sections: true,
+            ..ParserOptions::default()
+        };
+        assert_eq!(
+            ThethaCoreConfig::parse_with_options(input, &options).unwrap(),
+            config
+        );
+
+        let err = ThethaCoreConfig::parse("<a>\nx == 1\n  </b>").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::MismatchedSectionClose { line: 3, column: 3, ref found, open: Some(ref open) }
+                if found == "b" && open == "a"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 3, col 3: Close marker '</b>' doesn't match the open section 'a'"
+        );
+        let err = ThethaCoreConfig::parse("<a>\n</a>\n</a>").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 3, col 1: Close marker '</a>' has no open section"
+        );
+        let err = ThethaCoreConfig::parse("<a>\n</a>\nx == 1").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::ValueOutsideSection { line: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn test_strict_sections_require_close() {
+        let options = ParserOptions {
+            strict_sections: true,
+            ..ParserOptions::default()
+        };
+        let err =
+            ThethaCoreConfig::parse_with_options("<a>\n</a>\n<b>\nx == 1\n", &options).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnclosedSection { line: 3, column: 1, ref section } if section == "b"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Error on line 3, col 1: Section 'b' is never closed"
+        );
+
+        let err = ThethaCoreConfig::parse_with_options("<a>\n<b>\n</b>", &options).unwrap_err();
+        assert!(matches!(err, ParseError::UnclosedSection { line: 1, .. }));
+        assert!(ThethaCoreConfig::parse("<a>\n<b>\n</b>").is_ok());
+    }
+
+    #[test]
+    fn test_custom_separator() {
+        let input = r#"
+        <server>
+        host: "localhost"
+        port :8080
+        "quoted key" : True
+        url: "http://localhost:8080"
+        limits: { "body" == 1MB, "headers" == 100 }
+        empty:
+        "#;
+        let options = ParserOptions::new().separator(":");
+        let config = ThethaCoreConfig::parse_with_options(input, &options).unwrap();
+        assert_eq!(config.get_string("server", "host"), Some("localhost"));
+        assert_eq!(config.get_i64("server", "port"), Some(8080));
+        assert_eq!(config.get_bool("server", "quoted key"), Some(true));
+        // The first `:` separates, so later ones stay in the value.
+        assert_eq!(
+            config.get_string("server", "url"),
+            Some("http://localhost:8080")
+        );
+        let limits = config.get_value("server", "limits").unwrap();
+        assert_eq!(limits["headers"], Value::Integer(100));
+        assert_eq!(config.get_value("server", "empty"), Some(&Value::Null));
+
+        // `==` is no longer a separator, and nor is `=` reported as a slip for it.
+        let err = ThethaCoreConfig::parse_with_options("<a>\nx == 1", &options).unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedToken { line: 2, .. }));
+        let err = ThethaCoreConfig::parse_with_options("<a>\nx = 1", &options).unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedToken { line: 2, .. }));
+
+        let options = ParserOptions::new().separator("=");
+        let config =
+            ThethaCoreConfig::parse_with_options("<a>\nx = 1\ny=[1, 2]", &options).unwrap();
+        assert_eq!(config.get_i64("a", "x"), Some(1));
+        assert_eq!(config.get_i64_array("a", "y"), Some(vec![1, 2]));
+
+        // Separators that would make lines ambiguous are rejected.
+        for separator in [
+            "", " ", "-", ".", "_", "a", "é", "1", "[", "{", "<", "\"", "#", ";", "//", "/*", ": ",
+            "=>",
+        ] {
+            let options = ParserOptions::new().separator(separator);
+            match ThethaCoreConfig::parse_with_options("<a>\nkey 1", &options) {
+                Err(ParseError::InvalidSeparator { separator: found }) => {
+                    assert_eq!(found, separator)
+                }
+                other => panic!(
+                    "{:?}: expected an invalid separator, got {:?}",
+                    separator, other
+                ),
+            }
+        }
+        let options = ParserOptions::new().separator("->");
+        let err = ThethaCoreConfig::parse_multi_with_options("", &options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Error: Invalid separator '->'; it must not be empty or hold whitespace, key characters, quotes, brackets or comment markers"
+        );
+        for separator in [":", "=", ":=", "/", "@", "=="] {
+            let options = ParserOptions::new().separator(separator);
+            let input = format!("<a>\nkey {} 1", separator);
+            let config = ThethaCoreConfig::parse_with_options(&input, &options).unwrap();
+            assert_eq!(config.get_i64("a", "key"), Some(1), "{}", separator);
+        }
+    }
+
+    #[test]
+    fn test_require_non_empty_sections() {
+        let input = "<a>\nx == 1\n\n  <a<typo>>\n<b>\ny == 2\n";
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.section("a/typo").map(IndexMap::len), Some(0));
+
+        let options = ParserOptions::new().require_non_empty_sections(true);
+        let err = ThethaCoreConfig::parse_with_options(input, &options).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::EmptySection { line: 4, column: 3, ref section } if section == "a/typo"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Error on line 4, col 3: Section 'a/typo' has no keys"
+        );
+
+        // A section only needs keys in one of its blocks.
+        let input = "<a>\n<b>\ny == 2\n<a>\nx == 1\n";
+        assert!(ThethaCoreConfig::parse_with_options(input, &options).is_ok());
+        assert!(ThethaCoreConfig::parse_with_options("<a>\nx ==\n", &options).is_ok());
+    }
+
+    #[test]
+    fn test_block_comments() {
+        let input = r#"
+        <general>
+        name == "a /* not a comment */ b" /* trailing */
+        /*
+        <disabled>
+        key == "ignored"
+        */
+        port == /* inline */ 8080
+        /* one */ /* two */ debug == True
+        note == """
+        /* kept */
+        """
+        path == "x" // /* not opened here
+        tags == [
+            "a", /* "b",
+            "c", */
+            "d"
+        ]
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.sorted_section_names(), ["general"]);
+        assert_eq!(
+            config.get_string("general", "name"),
+            Some("a /* not a comment */ b")
+        );
+        assert_eq!(config.get_i64("general", "port"), Some(8080));
+        assert_eq!(config.get_bool("general", "debug"), Some(true));
+        assert_eq!(config.get_string("general", "note"), Some("/* kept */\n"));
+        assert_eq!(config.get_string("general", "path"), Some("x"));
+        assert_eq!(
+            config.get_string_array("general", "tags"),
+            Some(vec!["a".to_string(), "d".to_string()])
+        );
+
+        let err = ThethaCoreConfig::parse("<a>\nx == 1\n  /* open\ny == 2\n").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnterminatedBlockComment { line: 3, column: 3 }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 3, col 3: Unterminated block comment"
+        );
+        let err = ThethaCoreConfig::parse("<a>\nx == /* 1 */ @\n").unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { column: 14, .. }));
+    }
+
+    #[test]
+    fn test_section_header_comments() {
+        let input = r#"
+        <database> # primary db
+        host == "a"
+        <database<replica>> // read-only
+        host == "b"
+        <cache>; local
+        size == 1
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.sorted_section_names(),
+            ["cache", "database", "database/replica"]
+        );
+        assert_eq!(config.get_string("database/replica", "host"), Some("b"));
+
+        let err = ThethaCoreConfig::parse("<database # primary>").unwrap_err();
+        assert!(matches!(err, ParseError::UnterminatedSectionHeader { .. }));
+    }
+
+    #[test]
+    fn test_unterminated_section_headers() {
+        let err = ThethaCoreConfig::parse("<ok>\n  <database\nport == 1\n").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnterminatedSectionHeader {
+                line: 2,
+                column: 3,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 3: Unterminated section header '<database'"
+        );
+
+        let err = ThethaCoreConfig::parse("<ok>\ndatabase>\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 1: Unterminated section header 'database>'"
+        );
+
+        for header in ["<a<b", "<database>x", "database<advanced>>"] {
+            let err = ThethaCoreConfig::parse(header).unwrap_err();
+            assert!(
+                matches!(err, ParseError::UnterminatedSectionHeader { .. }),
+                "{}: got {}",
+                header,
+                err
+            );
+        }
+
+        let err = ThethaCoreConfig::parse("<a>b>").unwrap_err();
+        assert!(matches!(err, ParseError::InvalidSectionHeader { .. }));
+    }
+
+    #[test]
+    fn test_escaped_section_delimiters() {
+        let input = r#"
+        <Map\<K, V\>>
+        a == 1
+        </Map\<K, V\>>
+
+        <ops<\>\>>>
+        b == 2
+
+        <path\\name>
+        c == 3
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.get_i64("Map<K, V>", "a"), Some(1));
+        assert_eq!(config.get_i64("ops/>>", "b"), Some(2));
+        assert_eq!(config.get_i64("path\\name", "c"), Some(3));
+
+        // Other backslashes are kept as written.
+        let config = ThethaCoreConfig::parse("<a\\b>\nx == 1").unwrap();
+        assert_eq!(config.sorted_section_names(), ["a\\b"]);
+
+        for header in ["<a\\>", "<a<b\\>"] {
+            let err = ThethaCoreConfig::parse(header).unwrap_err();
+            assert!(
+                matches!(err, ParseError::UnterminatedSectionHeader { .. }),
+                "{}: got {}",
+                header,
+                err
+            );
+        }
+        let err = ThethaCoreConfig::parse("<a>b\\>>").unwrap_err();
+        assert!(matches!(err, ParseError::InvalidSectionHeader { .. }));
+    }
+
+    #[test]
+    fn test_empty_section_names() {
+        for header in ["<>", "< >", "<<>>", "<a<>>", "<a< >>", "<<b>>"] {
+            let input = format!("<ok>\n{}\nkey == 1\n", header);
+            let err = ThethaCoreConfig::parse(&input).unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    ParseError::EmptySectionName {
+                        line: 2,
+                        column: 1,
+                        ..
+                    }
+                ),
+                "{}: got {}",
+                header,
+                err
+            );
+        }
+
+        let err = ThethaCoreConfig::parse("<a<>>").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 1, col 1: Empty section name in header '<a<>>'"
+        );
+    }
+
+    #[test]
+    fn test_error_columns() {
+        let input = "<data>\n    items == [1, [2, @bad], 3]\n";
+        let err = ThethaCoreConfig::parse(input).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InvalidValue {
+                line: 2,
+                column: 22,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 22: Unable to parse value '@bad'"
+        );
+
+        let input = "<data>\nobj == { a == 1, broken }\n";
+        let err = ThethaCoreConfig::parse(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 18: Expected '==' between key and value in object pair 'broken'"
+        );
+
+        let input = "<data>\nobj == { a == \"x\\q\" }\n";
+        let err = ThethaCoreConfig::parse(input).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InvalidValue {
+                line: 2,
+                column: 17,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let input = "<database>\ntimeout == 10\nhost == \"a\"\ntimeout == 20\n";
+
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.sections.get("database").unwrap().get("timeout"),
+            Some(&Value::Integer(20))
+        );
+
+        let err = ThethaCoreConfig::parse_strict(input).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::DuplicateKey { line: 4, first_line: 2, ref key, .. } if key == "timeout"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Error on line 4, col 1: Duplicate key 'timeout' (first defined on line 2)"
+        );
+    }
+
+    #[test]
+    fn test_reopened_sections() {
+        let input = r#"
+        <database>
+        host == "localhost"
+
+        <cache>
+        ttl == 60
+
+        <database>
+        port == 5432
+        "#;
+        let config = ThethaCoreConfig::parse_strict(input).unwrap();
+        assert_eq!(config.get_string("database", "host"), Some("localhost"));
+        assert_eq!(config.get_i64("database", "port"), Some(5432));
+        assert_eq!(config.sorted_section_names(), vec!["cache", "database"]);
+        let keys: Vec<&String> = config.section("database").unwrap().keys().collect();
+        assert_eq!(keys, vec!["host", "port"]);
+
+        let input = "<database>\nport == 1\n<cache>\n<database>\nport == 2\n";
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.get_i64("database", "port"), Some(2));
+        let err = ThethaCoreConfig::parse_strict(input).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::DuplicateKey {
+                line: 5,
+                first_line: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_same_key_in_different_sections_is_not_duplicate() {
+        let input = "<a>\ntimeout == 10\n<b>\ntimeout == 20\n";
+        let config = ThethaCoreConfig::parse_strict(input).unwrap();
+        assert_eq!(config.sections.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_multi() {
+        let input = r#"
+        <server>
+        host == "a.example.com"
+        port == 8080
+        ---
+        port == 1
+        "#;
+        assert!(matches!(
+            ThethaCoreConfig::parse_multi(input),
+            Err(ParseError::ValueOutsideSection { line: 6, .. })
+        ));
+
+        let input = r#"
+        <server>
+        host == "a.example.com"
+        port == 8080
+          ---
+        <server>
+        host == "b.example.com"
+
+        <client>
+        retries == 3
+        "#;
+        let documents = ThethaCoreConfig::parse_multi(input).unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(
+            documents[0].get_string("server", "host"),
+            Some("a.example.com")
+        );
+        assert_eq!(documents[0].get_i64("server", "port"), Some(8080));
+        assert!(!documents[0].contains_section("client"));
+        assert_eq!(
+            documents[1].get_string("server", "host"),
+            Some("b.example.com")
+        );
+        assert_eq!(documents[1].get_i64("server", "port"), None);
+        assert_eq!(documents[1].get_i64("client", "retries"), Some(3));
+
+        let documents = ThethaCoreConfig::parse_multi("<a>\nx == 1\n---\n---\n").unwrap();
+        assert_eq!(documents.len(), 3);
+        assert!(documents[1].sections.is_empty() && documents[2].sections.is_empty());
+        assert_eq!(
+            ThethaCoreConfig::parse_multi("<a>\nx == 1").unwrap(),
+            [ThethaCoreConfig::parse("<a>\nx == 1").unwrap()]
+        );
+
+        // Separators inside multi-line values and block comments don't count.
+        let input = "<a>\ntext == \"\"\"\n---\n\"\"\"\n/*\n---\n*/\n---\n<b>\ny == 2";
+        let documents = ThethaCoreConfig::parse_multi(input).unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0].get_string("a", "text"), Some("---\n"));
+        assert!(documents[1].contains_section("b"));
+        assert!(matches!(
+            ThethaCoreConfig::parse_multi("<a>\nlist == [\n    1,\n---\n]"),
+            Err(ParseError::InvalidValue { line: 4, .. })
+        ));
+
+        let options = ParserOptions {
+            strict_duplicates: true,
+            separator: ":".to_string(),
+            ..ParserOptions::default()
+        };
+        let documents =
+            ThethaCoreConfig::parse_multi_with_options("<a>\nx: 1\n---\n<a>\nx: 2", &options)
+                .unwrap();
+        assert_eq!(documents[1].get_i64("a", "x"), Some(2));
+        assert!(matches!(
+            ThethaCoreConfig::parse_multi_with_options("---\n<a>\nx: 1\nx: 2", &options),
+            Err(ParseError::DuplicateKey { line: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_multi_documents_are_independent() {
+        // A `---` line in a triple-quoted string is part of the string.
+        let input = "<a>\ntext == \"\"\"\nbefore\n---\nafter\"\"\"\nx == 1";
+        let documents = ThethaCoreConfig::parse_multi(input).unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(
+            documents[0].get_string("a", "text"),
+            Some("before\n---\nafter")
+        );
+        assert_eq!(documents[0].get_i64("a", "x"), Some(1));
+
+        // Duplicates are checked within each document, not across them.
+        let options = ParserOptions {
+            strict_duplicates: true,
+            ..ParserOptions::default()
+        };
+        let input = "<a>\nx == 1\n---\n<a>\nx == 2\ny == 3\ny == 4";
+        match ThethaCoreConfig::parse_multi_with_options(input, &options) {
+            Err(ParseError::DuplicateKey {
+                line,
+                key,
+                first_line,
+                ..
+            }) => assert_eq!((line, key.as_str(), first_line), (7, "y", 6)),
+            other => panic!("expected a duplicate key, got {:?}", other),
+        }
+        let input = "<a>\nx == 1\n---\n<a>\nx == 2";
+        assert!(ThethaCoreConfig::parse_multi(input).is_ok());
+        assert_eq!(
+            ThethaCoreConfig::parse_multi_with_options(input, &options).unwrap()[1]
+                .get_i64("a", "x"),
+            Some(2)
+        );
+
+        // Lines are numbered through the whole input, counting the lines of a
+        // multi-line array in the first document.
+        let input = "<a>\nlist == [\n    1,\n    2,\n]\n---\n  key == 1";
+        assert!(matches!(
+            ThethaCoreConfig::parse_multi(input),
+            Err(ParseError::ValueOutsideSection {
+                line: 7,
+                column: 3,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_set_from_str() {
+        let mut config =
+            ThethaCoreConfig::parse("<database>\npool_size == 5\nhost == \"db\"").unwrap();
+        let arg = "--set database.pool_size=20";
+        let (path, raw) = arg.strip_prefix("--set ").unwrap().split_once('=').unwrap();
+        config.set_from_str(path, raw).unwrap();
+        assert_eq!(config.get_i64("database", "pool_size"), Some(20));
+        assert_eq!(config.get_string("database", "host"), Some("db"));
+
+        config.set_from_str("cache.advanced.ttl", " 30s ").unwrap();
+        config
+            .set_from_str("cache/advanced/hosts", r#"["a", "b"]"#)
+            .unwrap();
+        let section = config.section("cache/advanced").unwrap();
+        assert_eq!(section["ttl"], Value::Duration(Duration::from_secs(30)));
+        assert_eq!(
+            config.get_string_array("cache/advanced", "hosts"),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+
+        let err = config
+            .set_from_str("database.pool_size", "  [1, @]")
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::InvalidValue {
+                line: 1,
+                column: 7,
+                ..
+            }
+        ));
+        assert!(matches!(
+            config.set_from_str("pool_size", "1"),
+            Err(ParseError::ValueOutsideSection { .. })
+        ));
+        assert!(matches!(
+            config.set_from_str("database.", "1"),
+            Err(ParseError::ValueOutsideSection { .. })
+        ));
+        for path in ["a..b", "\"\".b", "a.\"\""] {
+            assert!(matches!(
+                config.set_from_str(path, "1"),
+                Err(ParseError::ValueOutsideSection { .. })
+            ));
+        }
+
+        // Quoted parts may hold dots and slashes; a section name can't hold a
+        // slash.
+        config
+            .set_from_str(r#"app."v1.2"."log.level""#, "\"debug\"")
+            .unwrap();
+        assert_eq!(config.get_string("app/v1.2", "log.level"), Some("debug"));
+        config.set_from_str(r#"app/"a/b \"c\" \\""#, "1").unwrap();
+        assert_eq!(config.get_i64("app", r#"a/b "c" \"#), Some(1));
+        for (path, column) in [
+            (r#"app."v1/2".key"#, 8),
+            (r#"app."v1.2"#, 5),
+            (r#"app."v1"x.key"#, 9),
+            (r#"app.v"1".key"#, 6),
+            (r#"app."\n""#, 6),
+        ] {
+            assert!(
+                matches!(
+                    config.set_from_str(path, "1"),
+                    Err(ParseError::UnexpectedToken { line: 1, column: c, .. }) if c == column
+                ),
+                "{path}"
+            );
+        }
+        assert_eq!(config.get_i64("database", "pool_size"), Some(20));
+    }
+
+    #[test]
+    fn test_set_from_str_quoted_paths() {
+        let mut config = ThethaCoreConfig::new();
+        config.set_from_str(r#""a.b".c"#, "1").unwrap();
+        config.set_from_str(r#"a."b.c""#, "2").unwrap();
+        assert_eq!(config.sorted_section_names(), ["a", "a.b"]);
+        assert_eq!(config.get_i64("a.b", "c"), Some(1));
+        assert_eq!(config.get_i64("a", "b.c"), Some(2));
+        assert!(!config.contains_section("a/b"));
+
+        let before = config.clone();
+        let message = |config: &mut ThethaCoreConfig, path| {
+            config.set_from_str(path, "1").unwrap_err().to_string()
+        };
+        for (path, column) in [
+            (r#""a/b".c"#, 3),
+            (r#""a.b"#, 1),
+            (r#""a"b.c"#, 4),
+            (r#"a"b".c"#, 2),
+            (r#""a\x".c"#, 3),
+        ] {
+            assert_eq!(
+                message(&mut config, path),
+                format!("❌ Syntax error on line 1, col {}: '{}'", column, path)
+            );
+        }
+        for path in ["c", r#""a.b""#, "a..c", "a.", ".c", r#""".c"#] {
+            assert_eq!(
+                message(&mut config, path),
+                "❌ Error on line 1, col 1: Key-value pair found outside of a section"
+            );
+        }
+        assert_eq!(config, before);
+    }
+
+    #[test]
+    fn test_parse_env_block() {
+        let env = r#"
+        # database settings
+        DB_HOST=localhost
+        DB_PORT = 5432
+        export SECRET="p@ss=word"
+        GREETING='hi there'
+        EMPTY=
+        "#;
+        let mut config = ThethaCoreConfig::parse("<env>\nDB_HOST == \"old\"\nkept == 1").unwrap();
+        config.parse_env_block("env", env).unwrap();
+        let section = config.section("env").unwrap();
+        let keys: Vec<&str> = section.keys().map(String::as_str).collect();
+        assert_eq!(
+            keys,
+            ["DB_HOST", "kept", "DB_PORT", "SECRET", "GREETING", "EMPTY"]
+        );
+        assert_eq!(config.get_string("env", "DB_HOST"), Some("localhost"));
+        assert_eq!(config.get_string("env", "DB_PORT"), Some("5432"));
+        assert_eq!(config.get_string("env", "SECRET"), Some("p@ss=word"));
+        assert_eq!(config.get_string("env", "GREETING"), Some("hi there"));
+        assert_eq!(config.get_string("env", "EMPTY"), Some(""));
+        assert_eq!(config.get_i64("env", "kept"), Some(1));
+
+        let mut config = ThethaCoreConfig::new();
+        let err = config
+            .parse_env_block("env", "A=1\n  not a pair")
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnexpectedToken {
+                line: 2,
+                column: 3,
+                ..
+            }
+        ));
+        assert!(config.sections.is_empty());
+    }
+
+    #[test]
+    fn test_env_interpolation() {
+        env::set_var("THETHAC_TEST_DB_HOST", "db.internal");
+        env::remove_var("THETHAC_TEST_UNSET");
+        let input = r#"
+        <database>
+        url == "${THETHAC_TEST_DB_HOST}:5432"
+        fallback == "${THETHAC_TEST_UNSET:-localhost}"
+        nested == ["${THETHAC_TEST_DB_HOST}", { "x" == "${THETHAC_TEST_UNSET:-}" }]
+        "#;
+
+        let config = ThethaCoreConfig::parse_with_env(input).unwrap();
+        let db = config.sections.get("database").unwrap();
+        assert_eq!(
+            db.get("url"),
+            Some(&Value::String("db.internal:5432".to_string()))
+        );
+        assert_eq!(
+            db.get("fallback"),
+            Some(&Value::String("localhost".to_string()))
+        );
+        assert_eq!(
+            db.get("nested"),
+            Some(&Value::Array(vec![
+                Value::String("db.internal".to_string()),
+                Value::Object(IndexMap::from([(
+                    "x".to_string(),
+                    Value::String(String::new())
+                )])),
+            ]))
+        );
+
+        // Plain parsing leaves references untouched.
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.sections.get("database").unwrap().get("url"),
+            Some(&Value::String("${THETHAC_TEST_DB_HOST}:5432".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_env_interpolation_missing_variable() {
+        env::remove_var("THETHAC_TEST_MISSING");
+        let input = "<database>\nurl == \"${THETHAC_TEST_MISSING}\"\n";
+        let err = ThethaCoreConfig::parse_with_env(input).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UndefinedVariable { line: 2, column: 8, ref name } if name == "THETHAC_TEST_MISSING"
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Error on line 2, col 8: Environment variable 'THETHAC_TEST_MISSING' is not set"
+        );
+    }
+
+    /// Create a fresh temporary directory for file-based tests.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("thethac-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = temp_dir("include");
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        fs::write(
+            dir.join("shared/common.thtc"),
+            "<database>\nhost == \"localhost\"\nport == 5432\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("main.thtc"),
+            "<database>\nport == 1\n@include \"shared/common.thtc\"\nhost == \"db.prod\"\n",
+        )
+        .unwrap();
+
+        let config =
+            ThethaCoreConfig::parse_from_file(dir.join("main.thtc").to_str().unwrap()).unwrap();
+        let db = config.sections.get("database").unwrap();
+        assert_eq!(db.get("host"), Some(&Value::String("db.prod".to_string())));
+        assert_eq!(db.get("port"), Some(&Value::Integer(5432)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_include_strict_duplicates() {
+        let dir = temp_dir("include-strict");
+        fs::write(dir.join("common.thtc"), "<db>\nhost == \"h\"\nport == 1\n").unwrap();
+        let options = ParserOptions {
+            strict_duplicates: true,
+            ..ParserOptions::default()
+        };
+        let parse = |main: &str| {
+            fs::write(dir.join("main.thtc"), main).unwrap();
+            ThethaCoreConfig::parse_file_inner(
+                &dir.join("main.thtc"),
+                &options,
+                &mut HashSet::new(),
+            )
+        };
+        let duplicate = |err: ParseError| match err {
+            ParseError::InFile { error, .. } => match *error {
+                ParseError::DuplicateKey {
+                    line,
+                    key,
+                    first_line,
+                    ..
+                } => (line, key, first_line),
+                error => panic!("Expected a duplicate key, got {:?}", error),
+            },
+            err => panic!("Expected an error in a file, got {:?}", err),
+        };
+
+        // An include may not redefine a key, nor have one redefined after it.
+        let err = parse("<db>\nport == 2\n@include \"common.thtc\"\n").unwrap_err();
+        assert_eq!(duplicate(err), (3, "port".to_string(), 2));
+        let err = parse("@include \"common.thtc\"\n<db>\nhost == \"x\"\n").unwrap_err();
+        assert_eq!(duplicate(err), (3, "host".to_string(), 1));
+
+        let config = parse("<db>\nuser == \"u\"\n@include \"common.thtc\"\n").unwrap();
+        assert_eq!(config.get_i64("db", "port"), Some(1));
+        assert_eq!(config.get_string("db", "user"), Some("u"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_include_cycle() {
+        let dir = temp_dir("include-cycle");
+        fs::write(dir.join("a.thtc"), "@include \"b.thtc\"\n").unwrap();
+        fs::write(dir.join("b.thtc"), "<b>\n@include \"a.thtc\"\n").unwrap();
+
+        let err =
+            ThethaCoreConfig::parse_from_file(dir.join("a.thtc").to_str().unwrap()).unwrap_err();
+        let b_path = dir.join("b.thtc").display().to_string();
+        assert_eq!(err.source_path(), Some(b_path.as_str()));
+        let ParseError::InFile { ref error, .. } = err else {
+            panic!("Expected an error in a file, got {:?}", err);
+        };
+        assert!(
+            matches!(**error, ParseError::InvalidInclude { line: 2, ref path, .. } if path == "a.thtc")
+        );
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "❌ Error in '{}' on line 2, col 1: Cannot include 'a.thtc': File is already included",
+                b_path
+            )
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_errors_name_the_file() {
+        let dir = temp_dir("error-path");
+        fs::create_dir_all(dir.join("config")).unwrap();
+        let path = dir.join("config/db.thtc");
+        fs::write(&path, "<db>\nhost == \"h\"\nport = 1\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let err = ThethaCoreConfig::parse_from_file(path).unwrap_err();
+        assert_eq!(err.source_path(), Some(path));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "❌ Syntax error in '{}' on line 3, col 6: Found '=' in 'port = 1'; use '==' to assign a value",
+                path
+            )
+        );
+        let err = ThethaCoreConfig::parse("port = 1").unwrap_err();
+        assert_eq!(err.source_path(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_include_requires_file() {
+        let err = ThethaCoreConfig::parse("@include \"common.thtc\"").unwrap_err();
+        assert!(matches!(err, ParseError::InvalidInclude { line: 1, .. }));
+    }
+
+    #[test]
+    fn test_float_forms() {
+        let input = r#"
+        <data>
+        avogadro == 6.02e23
+        tiny == 1E-9
+        rate == inf
+        neg == -INF
+        unknown == NaN
+        half == .5
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let data = config.sections.get("data").unwrap();
+        assert_eq!(data.get("avogadro"), Some(&Value::Float(6.02e23)));
+        assert_eq!(data.get("tiny"), Some(&Value::Float(1e-9)));
+        assert_eq!(data.get("rate"), Some(&Value::Float(f64::INFINITY)));
+        assert_eq!(data.get("neg"), Some(&Value::Float(f64::NEG_INFINITY)));
+        assert!(matches!(data.get("unknown"), Some(Value::Float(n)) if n.is_nan()));
+        assert_eq!(data.get("half"), Some(&Value::Float(0.5)));
+    }
+
+    #[test]
+    fn test_invalid_float_forms() {
+        for value in ["1e", "1.2.3", "e5", "infinite", "nan1"] {
+            let input = format!("<data>\nx == {}\n", value);
+            assert!(
+                matches!(
+                    ThethaCoreConfig::parse(&input),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "expected '{}' to be rejected",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_radix_integers() {
+        let input = r#"
+        <data>
+        flags == 0xFF
+        mode == 0o755
+        mask == 0b1010
+        offset == -0x10
+        plain == 0
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let data = config.sections.get("data").unwrap();
+        assert_eq!(data.get("flags"), Some(&Value::Integer(255)));
+        assert_eq!(data.get("mode"), Some(&Value::Integer(0o755)));
+        assert_eq!(data.get("mask"), Some(&Value::Integer(10)));
+        assert_eq!(data.get("offset"), Some(&Value::Integer(-16)));
+        assert_eq!(data.get("plain"), Some(&Value::Integer(0)));
+    }
+
+    #[test]
+    fn test_invalid_radix_integers() {
+        let err = ThethaCoreConfig::parse("<data>\nflags == 0xZZ\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 10: Invalid hexadecimal integer '0xZZ'"
+        );
+        for value in ["0o8", "0b102", "0x"] {
+            let input = format!("<data>\nx == {}\n", value);
+            assert!(
+                ThethaCoreConfig::parse(&input).is_err(),
+                "expected '{}' to be rejected",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_digit_separators() {
+        let input = r#"
+        <data>
+        max_rows == 1_000_000
+        ratio == 1.234_567
+        mask == 0b1010_1010
+        color == 0xFF_00_FF
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let data = config.sections.get("data").unwrap();
+        assert_eq!(data.get("max_rows"), Some(&Value::Integer(1_000_000)));
+        assert_eq!(data.get("ratio"), Some(&Value::Float(1.234_567)));
+        assert_eq!(data.get("mask"), Some(&Value::Integer(0b1010_1010)));
+        assert_eq!(data.get("color"), Some(&Value::Integer(0xFF00FF)));
+    }
+
+    #[test]
+    fn test_misplaced_digit_separators() {
+        for value in ["_100", "100_", "1__0", "1_.5", "0x_FF", "0b1010_"] {
+            let input = format!("<data>\nx == {}\n", value);
+            let err = ThethaCoreConfig::parse(&input).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "❌ Syntax error on line 2, col 6: Misplaced digit separator in '{}'",
+                    value
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_signed_numbers() {
+        let input = r#"
+        <data>
+        temp == -5
+        boost == +5
+        rate == -3.5e2
+        gain == +0.25
+        offset == +0x10
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let data = config.sections.get("data").unwrap();
+        assert_eq!(data.get("temp"), Some(&Value::Integer(-5)));
+        assert_eq!(data.get("boost"), Some(&Value::Integer(5)));
+        assert_eq!(data.get("rate"), Some(&Value::Float(-350.0)));
+        assert_eq!(data.get("gain"), Some(&Value::Float(0.25)));
+        assert_eq!(data.get("offset"), Some(&Value::Integer(16)));
+    }
+
+    #[test]
+    fn test_invalid_signs() {
+        for value in ["-", "+", "--5", "+-5", "5-", "-.e1"] {
+            let input = format!("<data>\nx == {}\n", value);
+            let err = ThethaCoreConfig::parse(&input).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "❌ Syntax error on line 2, col 6: Unable to parse value '{}'",
+                    value
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_integer_overflow() {
+        let input = "<data>\nbig == 1234567890123456789012345\n";
+        let err = ThethaCoreConfig::parse(input).unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::IntegerOverflow {
+                line: 2,
+                column: 8,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 8: Integer '1234567890123456789012345' is out of range for i64"
+        );
+
+        for value in ["-9999999999999999999", "0x1_0000_0000_0000_0000"] {
+            let input = format!("<data>\nx == {}\n", value);
+            let err = ThethaCoreConfig::parse(&input).unwrap_err();
+            assert!(
+                matches!(err, ParseError::IntegerOverflow { .. }),
+                "{}",
+                value
+            );
+        }
+
+        let config = ThethaCoreConfig::parse("<data>\nmin == -9223372036854775808\n").unwrap();
+        assert_eq!(config.get_i64("data", "min"), Some(i64::MIN));
+    }
+
+    #[test]
+    fn test_value_as_helpers() {
+        let string = Value::String("hi".to_string());
+        let array = Value::Array(vec![Value::Integer(1)]);
+        let object = Value::Object(IndexMap::from([("a".to_string(), Value::Null)]));
+
+        assert_eq!(string.as_str(), Some("hi"));
+        assert_eq!(Value::Integer(3).as_i64(), Some(3));
+        assert_eq!(Value::Float(1.5).as_f64(), Some(1.5));
+        assert_eq!(Value::Boolean(true).as_bool(), Some(true));
+        assert_eq!(array.as_array(), Some(&[Value::Integer(1)][..]));
+        assert_eq!(object.as_object().map(|o| o.len()), Some(1));
+
+        assert_eq!(Value::Integer(3).as_str(), None);
+        assert_eq!(Value::Float(1.0).as_i64(), None);
+        assert_eq!(Value::Integer(1).as_f64(), None);
+        assert_eq!(Value::Null.as_bool(), None);
+        assert_eq!(object.as_array(), None);
+        assert_eq!(array.as_object(), None);
+    }
+
+    #[test]
+    fn test_value_is_predicates() {
+        let values = [
+            Value::String("hi".to_string()),
+            Value::Integer(1),
+            Value::Float(1.5),
+            Value::Boolean(false),
+            Value::Null,
+            Value::Array(vec![]),
+            Value::Object(IndexMap::new()),
+            Value::Duration(Duration::from_secs(1)),
+        ];
+        type Predicate = fn(&Value) -> bool;
+        let predicates: [(Predicate, &[usize]); 8] = [
+            (Value::is_string, &[0]),
+            (Value::is_integer, &[1]),
+            (Value::is_float, &[2]),
+            (Value::is_number, &[1, 2]),
+            (Value::is_bool, &[3]),
+            (Value::is_null, &[4]),
+            (Value::is_array, &[5]),
+            (Value::is_object, &[6]),
+        ];
+        for (i, (predicate, matching)) in predicates.iter().enumerate() {
+            for (j, value) in values.iter().enumerate() {
+                assert_eq!(
+                    predicate(value),
+                    matching.contains(&j),
+                    "predicate {} on {:?}",
+                    i,
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_value_in_range_helpers() {
+        assert_eq!(Value::Integer(8080).as_i64_in_range(1, 65535), Some(8080));
+        assert_eq!(Value::Integer(1).as_i64_in_range(1, 65535), Some(1));
+        assert_eq!(Value::Integer(65535).as_i64_in_range(1, 65535), Some(65535));
+        assert_eq!(Value::Integer(0).as_i64_in_range(1, 65535), None);
+        assert_eq!(Value::Integer(70000).as_i64_in_range(1, 65535), None);
+        assert_eq!(Value::Float(80.0).as_i64_in_range(1, 65535), None);
+        assert_eq!(
+            Value::String("80".to_string()).as_i64_in_range(1, 65535),
+            None
+        );
+
+        assert_eq!(Value::Float(0.5).as_f64_in_range(0.0, 1.0), Some(0.5));
+        assert_eq!(Value::Float(1.0).as_f64_in_range(0.0, 1.0), Some(1.0));
+        assert_eq!(Value::Float(-0.1).as_f64_in_range(0.0, 1.0), None);
+        assert_eq!(Value::Float(f64::NAN).as_f64_in_range(0.0, 1.0), None);
+        assert_eq!(Value::Integer(1).as_f64_in_range(0.0, 1.0), None);
+        assert_eq!(Value::Null.as_f64_in_range(0.0, 1.0), None);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_datetimes() {
+        use chrono::{TimeZone, Utc};
+
+        let input = r#"
+        <schedule>
+        start == 2024-01-15T09:30:00Z
+        local == 2024-01-15T09:30:00.250+02:00
+        quoted == "2024-01-15T09:30:00Z"
+        list == [2024-01-15T09:30:00Z]
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let start = Utc.with_ymd_and_hms(2024, 1, 15, 9, 30, 0).unwrap();
+        assert_eq!(
+            config
+                .get_value("schedule", "start")
+                .and_then(Value::as_datetime),
+            Some(start)
+        );
+        let local = config.get_value("schedule", "local").unwrap();
+        assert_eq!(
+            local.as_datetime(),
+            Some(Utc.with_ymd_and_hms(2024, 1, 15, 7, 30, 0).unwrap() + Duration::from_millis(250))
+        );
+        assert_eq!(local.type_name(), "datetime");
+        assert_eq!(local.to_string(), "2024-01-15T07:30:00.250Z");
+        assert_eq!(
+            config.get_string("schedule", "quoted"),
+            Some("2024-01-15T09:30:00Z")
+        );
+        assert_eq!(
+            config.get_value("schedule", "list"),
+            Some(&Value::Array(vec![Value::DateTime(start)]))
+        );
+
+        let reparsed = ThethaCoreConfig::parse(&config.to_string()).unwrap();
+        assert_eq!(config, reparsed);
+
+        for near_miss in ["2024-01-15", "2024-13-15T09:30:00Z", "2024-01-15T09:30:00"] {
+            let input = format!("<s>\nt == {}", near_miss);
+            assert!(ThethaCoreConfig::parse(&input).is_err(), "{}", near_miss);
+        }
+    }
+
+    #[test]
+    fn test_raw_values() {
+        let input = r#"
+        <data>
+        flag == `True
+        version == `1.0 # a comment still ends the value
+        spaced == `  two words
+        list == [`1, 2, `Null]
+        object == { "id" == `007 }
+        empty == `
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let data = config.sections.get("data").unwrap();
+        assert_eq!(data.get("flag"), Some(&Value::String("True".to_string())));
+        assert_eq!(data.get("version"), Some(&Value::String("1.0".to_string())));
+        assert_eq!(
+            data.get("spaced"),
+            Some(&Value::String("  two words".to_string()))
+        );
+        assert_eq!(
+            data.get("list"),
+            Some(&Value::Array(vec![
+                Value::String("1".to_string()),
+                Value::Integer(2),
+                Value::String("Null".to_string()),
+            ]))
+        );
+        assert_eq!(
+            data.get("object"),
+            Some(&Value::Object(IndexMap::from([(
+                "id".to_string(),
+                Value::String("007".to_string())
+            )])))
+        );
+        assert_eq!(data.get("empty"), Some(&Value::String(String::new())));
+    }
+
+    #[test]
+    fn test_raw_values_with_quotes_and_comment_markers() {
+        let input = r#"
+        <data>
+        apostrophe == `don't # a comment
+        quoted == `say "hi" ; a comment
+        semicolon == `a;b
+        markers == `a#b//c
+        commented == `a # c
+        block == `x /* gone */
+        list == [`it's, `"q", `a;b # c
+            , `d'e]
+        object == { "k" == `o'k, "n" == 1 }
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let get = |key| config.get_string("data", key);
+        assert_eq!(get("apostrophe"), Some("don't"));
+        assert_eq!(get("quoted"), Some("say \"hi\""));
+        assert_eq!(get("semicolon"), Some("a;b"));
+        assert_eq!(get("markers"), Some("a#b//c"));
+        assert_eq!(get("commented"), Some("a"));
+        assert_eq!(get("block"), Some("x"));
+        assert_eq!(
+            config.get_string_array("data", "list"),
+            Some(vec![
+                "it's".to_string(),
+                "\"q\"".to_string(),
+                "a;b".to_string(),
+                "d'e".to_string()
+            ])
+        );
+        let object = config.get_value("data", "object").unwrap();
+        assert_eq!(object["k"].as_str(), Some("o'k"));
+        assert_eq!(object["n"], Value::Integer(1));
+    }
+
+    #[test]
+    fn test_parse_with_warnings() {
+        let input = r#"
+        <empty>
+        <db>
+        port == 1
+        port == 2
+        <later>
+        <db>
+        port == 3
+        "#;
+        let (config, warnings) = ThethaCoreConfig::parse_with_warnings(input).unwrap();
+        assert_eq!(config, ThethaCoreConfig::parse(input).unwrap());
+        let messages: Vec<String> = warnings.iter().map(Warning::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "⚠️ Warning on line 2: Section 'empty' has no keys",
+                "⚠️ Warning on line 5: Key 'port' was already defined on line 4; the last definition wins",
+                "⚠️ Warning on line 6: Section 'later' has no keys",
+                "⚠️ Warning on line 8: Key 'port' was already defined on line 4; the last definition wins",
+            ]
+        );
+
+        let (_, warnings) = ThethaCoreConfig::parse_with_warnings("<a>\nx == 1").unwrap();
+        assert!(warnings.is_empty());
+        assert!(ThethaCoreConfig::parse_with_warnings("x = 1").is_err());
+    }
+
+    #[test]
+    fn test_type_name() {
+        let cases = [
+            (Value::String("x".to_string()), "string"),
+            (Value::Integer(1), "integer"),
+            (Value::Float(1.5), "float"),
+            (Value::Boolean(true), "boolean"),
+            (Value::Null, "null"),
+            (Value::Array(vec![]), "array"),
+            (Value::Object(IndexMap::new()), "object"),
+            (Value::Duration(Duration::from_secs(1)), "duration"),
+            (Value::Bytes(1), "bytes"),
+        ];
+        for (value, name) in cases {
+            assert_eq!(value.type_name(), name);
+        }
+    }
+
+    #[test]
+    fn test_value_index() {
+        let config = ThethaCoreConfig::parse(
+            "<data>\nv == { \"servers\" == [{ \"host\" == \"a\" }, { \"host\" == \"b\" }] }",
+        )
+        .unwrap();
+        let v = config.get_value("data", "v").unwrap();
+        assert_eq!(v["servers"][1]["host"], Value::String("b".to_string()));
+        assert_eq!(v["servers"][0]["host"].as_str(), Some("a"));
+        let key = "servers".to_string();
+        assert_eq!(v[&key].as_array().map(<[Value]>::len), Some(2));
+
+        assert_eq!(
+            v.get("servers").and_then(|s| s.get(0)),
+            Some(&v["servers"][0])
+        );
+        assert_eq!(v.get("missing"), None);
+        assert_eq!(v["servers"].get(5), None);
+        assert_eq!(v.get(0), None);
+        assert_eq!(v["servers"].get("host"), None);
+    }
+
+    #[test]
+    fn test_value_get_path() {
+        let config = ThethaCoreConfig::parse(
+            "<data>\nv == { \"servers\" == [{ \"host\" == \"a\", \"ports\" == [80, 443] }], \"1\" == True }",
+        )
+        .unwrap();
+        let v = config.get_value("data", "v").unwrap();
+        assert_eq!(
+            v.get_path("servers.0.host").and_then(Value::as_str),
+            Some("a")
+        );
+        assert_eq!(
+            v.get_path("servers.0.ports.1").and_then(Value::as_i64),
+            Some(443)
+        );
+        assert_eq!(v.get_path("servers.0"), Some(&v["servers"][0]));
+        assert_eq!(v.get_path("1").and_then(Value::as_bool), Some(true));
+
+        assert_eq!(v.get_path("servers.1.host"), None);
+        assert_eq!(v.get_path("servers.first"), None);
+        assert_eq!(v.get_path("servers.0.host.name"), None);
+        assert_eq!(v.get_path("missing"), None);
+        assert_eq!(v.get_path(""), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "no index \"missing\" in object value")]
+    fn test_value_index_missing_key_panics() {
+        let _ = &Value::Object(IndexMap::new())["missing"];
+    }
+
+    #[test]
+    #[should_panic(expected = "no index 3 in array value")]
+    fn test_value_index_out_of_bounds_panics() {
+        let _ = &Value::Array(vec![Value::Null])[3];
+    }
+
+    #[test]
+    fn test_value_hash_matches_eq() {
+        let hash = |value: &Value| {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+        let pairs = [
+            (Value::Float(0.0), Value::Float(-0.0)),
+            (
+                Value::Array(vec![Value::Float(-0.0)]),
+                Value::Array(vec![Value::Float(0.0)]),
+            ),
+            (
+                parse_value(
+                    r#"{ "a" == 0.0, "b" == 1 }"#,
+                    &ParserOptions::default(),
+                    1,
+                    1,
+                )
+                .unwrap(),
+                parse_value(
+                    r#"{ "b" == 1, "a" == -0.0 }"#,
+                    &ParserOptions::default(),
+                    1,
+                    1,
+                )
+                .unwrap(),
+            ),
+        ];
+        for (a, b) in pairs {
+            assert_eq!(a, b);
+            assert_eq!(hash(&a), hash(&b), "{} and {}", a, b);
+        }
+        assert_ne!(hash(&Value::Float(0.0)), hash(&Value::Integer(0)));
+    }
+
+    #[test]
+    fn test_dedup_array() {
+        let value = |s| parse_value(s, &ParserOptions::default(), 1, 1).unwrap();
+        let mut tags = value(r#"["web", "db", "web", 1, 1.0, 1, "db"]"#);
+        tags.dedup_array();
+        assert_eq!(tags, value(r#"["web", "db", 1, 1.0]"#));
+
+        let mut scalar = Value::Integer(1);
+        scalar.dedup_array();
+        assert_eq!(scalar, Value::Integer(1));
+    }
+
+    #[test]
+    fn test_sort_array() {
+        let value = |s| parse_value(s, &ParserOptions::default(), 1, 1).unwrap();
+        let mut values = value(
+            r#"[{ "a" == 1 }, "b", [2], 1.5, 5s, Null, "a", 2, [1, 2], True, 1KB, False, -3]"#,
+        );
+        values.sort_array();
+        assert_eq!(
+            values,
+            value(
+                r#"[Null, False, True, -3, 1.5, 2, "a", "b", 5s, 1KB, [1, 2], [2], { "a" == 1 }]"#
+            )
+        );
+
+        // Integers and floats that compare equal keep their original order.
+        let mut numbers = value("[2.0, 1, 2, 1.0]");
+        numbers.sort_array();
+        assert!(matches!(
+            numbers.as_array().unwrap(),
+            [
+                Value::Integer(1),
+                Value::Float(_),
+                Value::Float(_),
+                Value::Integer(2)
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_merge_object() {
+        let base = parse_value(
+            r#"{ "db" == { "host" == "localhost", "port" == 5432 }, "tags" == ["a"] }"#,
+            &ParserOptions::default(),
+            1,
+            1,
+        )
+        .unwrap();
+        let overlay = parse_value(
+            r#"{ "db" == { "host" == "db.prod", "ssl" == True }, "tags" == ["b"], "new" == 1 }"#,
+            &ParserOptions::default(),
+            1,
+            1,
+        )
+        .unwrap();
+
+        let replaced = base
+            .clone()
+            .merge_object(overlay.clone(), ArrayMerge::Replace);
+        assert_eq!(
+            replaced.to_string(),
+            r#"{ "db" == { "host" == "db.prod", "port" == 5432, "ssl" == True }, "tags" == ["b"], "new" == 1 }"#
+        );
+        let concatenated = base.merge_object(overlay, ArrayMerge::Concat);
+        assert_eq!(
+            concatenated.as_object().unwrap().get("tags"),
+            Some(&Value::Array(vec![
+                Value::String("a".to_string()),
+                Value::String("b".to_string()),
+            ]))
+        );
+
+        assert_eq!(
+            Value::Integer(1).merge_object(Value::Integer(2), ArrayMerge::Concat),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            Value::Array(vec![Value::Null]).merge_object(Value::Array(vec![]), ArrayMerge::Concat),
+            Value::Array(vec![])
+        );
+    }
+
+    #[test]
+    fn test_dotted_and_hyphenated_keys() {
+        let input = r#"
+        <server>
+        max-connections == 5
+        log.level == "debug"
+        limits == { max-body.size == 1024 }
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.get_i64("server", "max-connections"), Some(5));
+        assert_eq!(config.get_string("server", "log.level"), Some("debug"));
+        assert_eq!(
+            config.get_value("server", "limits"),
+            Some(&Value::Object(IndexMap::from([(
+                "max-body.size".to_string(),
+                Value::Integer(1024)
+            )])))
+        );
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        let err = ThethaCoreConfig::parse("<server>\nmax connections == 5\n").unwrap_err();
+        assert!(matches!(err, ParseError::UnexpectedToken { line: 2, .. }));
+
+        let err = ThethaCoreConfig::parse("<server>\nlimits == { max body == 1 }\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 13: Invalid object key 'max body'"
+        );
+    }
+
+    #[test]
+    fn test_quoted_keys() {
+        let input = r#"
+        <headers>
+        "Content-Type" == "application/json"
+        "X Custom \"Header\"" == 1
+        'single quoted' == 2
+        plain == 3
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.get_string("headers", "Content-Type"),
+            Some("application/json")
+        );
+        assert_eq!(config.get_i64("headers", "X Custom \"Header\""), Some(1));
+        assert_eq!(config.get_i64("headers", "single quoted"), Some(2));
+        assert_eq!(config.get_i64("headers", "plain"), Some(3));
+    }
+
+    #[test]
+    fn test_inline_comments() {
+        let input = r##"
+        <server>
+        port == 8080 # production port
+        host == "example.com" // primary host
+        channel == "#general" # the "#" inside quotes stays
+        url == 'https://example.com/#top'
+        tags == ["a#b", "c"] # trailing
+        "##;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.get_i64("server", "port"), Some(8080));
+        assert_eq!(config.get_string("server", "host"), Some("example.com"));
+        assert_eq!(config.get_string("server", "channel"), Some("#general"));
+        assert_eq!(
+            config.get_string("server", "url"),
+            Some("https://example.com/#top")
+        );
+        assert_eq!(
+            config.get_value("server", "tags"),
+            Some(&Value::Array(vec![
+                Value::String("a#b".to_string()),
+                Value::String("c".to_string()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_semicolon_comments() {
+        let input = r#"
+        ; INI-style comment
+        <general>
+          ; indented comment
+        name == "a;b" ; trailing comment
+        list == ['x;y', 2]; no space before
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.get_string("general", "name"), Some("a;b"));
+        assert_eq!(
+            config.get_value("general", "list"),
+            Some(&Value::Array(vec![
+                Value::String("x;y".to_string()),
+                Value::Integer(2),
+            ]))
+        );
+        assert_eq!(config.section("general").map(|s| s.len()), Some(2));
+    }
+
+    #[test]
+    fn test_blank_values_are_null() {
+        let input = "<server>\npassword ==\nport == # nothing\ntoken ==   \nname == \"\"\n";
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.get_value("server", "password"), Some(&Value::Null));
+        assert_eq!(config.get_value("server", "port"), Some(&Value::Null));
+        assert_eq!(config.get_value("server", "token"), Some(&Value::Null));
+        assert_eq!(config.get_string("server", "name"), Some(""));
+        assert_eq!(config.get_value("server", "missing"), None);
+    }
+
+    #[test]
+    fn test_triple_quoted_strings() {
+        let input = r#"
+        <db>
+        query == """
+            SELECT *
+              FROM users # not a comment
+            WHERE id = "1"
+            """
+        inline == """say "hi" \n"""   # comment
+        count == 1
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.get_string("db", "query"),
+            Some("SELECT *\n  FROM users # not a comment\nWHERE id = \"1\"\n")
+        );
+        assert_eq!(config.get_string("db", "inline"), Some("say \"hi\" \\n"));
+        assert_eq!(config.get_i64("db", "count"), Some(1));
+    }
+
+    #[test]
+    fn test_triple_quoted_string_errors() {
+        let err = ThethaCoreConfig::parse("<db>\nquery == \"\"\"\nSELECT 1\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 10: Unterminated triple-quoted string '\"\"\"'"
+        );
+
+        let err =
+            ThethaCoreConfig::parse("<db>\nquery == \"\"\"\nSELECT 1\n\"\"\" oops\n").unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::UnexpectedToken {
+                line: 4,
+                column: 5,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_lenient_booleans() {
+        let input = r#"
+        <flags>
+        a == true
+        b == FALSE
+        c == Yes
+        d == no
+        e == ON
+        f == Off
+        g == [on, off]
+        h == True
+        "#;
+        let options = ParserOptions {
+            lenient_booleans: true,
+            ..ParserOptions::default()
+        };
+        let config = ThethaCoreConfig::parse_with_options(input, &options).unwrap();
+        let flags = config.sections.get("flags").unwrap();
+        let expected = [
+            ("a", true),
+            ("b", false),
+            ("c", true),
+            ("d", false),
+            ("e", true),
+            ("f", false),
+            ("h", true),
+        ];
+        for (key, expected) in expected {
+            assert_eq!(flags.get(key), Some(&Value::Boolean(expected)), "{}", key);
+        }
+        assert_eq!(
+            flags.get("g"),
+            Some(&Value::Array(vec![
+                Value::Boolean(true),
+                Value::Boolean(false)
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_lenient_lowercase_literals() {
+        let input = r#"
+        <data>
+        a == null
+        b == true
+        c == false
+        d == [null, true, { "x" == false }]
+        e == Null
+        f == False
+        g == "null"
+        "#;
+        let options = ParserOptions {
+            lenient_booleans: true,
+            ..ParserOptions::default()
+        };
+        let config = ThethaCoreConfig::parse_with_options(input, &options).unwrap();
+        let data = config.sections.get("data").unwrap();
+        assert_eq!(data.get("a"), Some(&Value::Null));
+        assert_eq!(data.get("b"), Some(&Value::Boolean(true)));
+        assert_eq!(data.get("c"), Some(&Value::Boolean(false)));
+        assert_eq!(
+            data.get("d"),
+            Some(&Value::Array(vec![
+                Value::Null,
+                Value::Boolean(true),
+                Value::Object(IndexMap::from([("x".to_string(), Value::Boolean(false))])),
+            ]))
+        );
+        assert_eq!(data.get("e"), Some(&Value::Null));
+        assert_eq!(data.get("f"), Some(&Value::Boolean(false)));
+        assert_eq!(data.get("g"), Some(&Value::String("null".to_string())));
+    }
+
+    #[test]
+    fn test_lenient_booleans_off_by_default() {
+        for value in ["true", "FALSE", "yes", "on", "null"] {
+            let input = format!("<flags>\na == {}", value);
+            assert!(
+                ThethaCoreConfig::parse(&input).is_err(),
+                "{} should not parse",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_durations() {
+        let input = r#"
+        <timeouts>
+        connect == 30s
+        session == 1h30m
+        poll == 250ms
+        mixed == 1d2h3m4s5ms6us7ns
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let timeouts = config.sections.get("timeouts").unwrap();
+        assert_eq!(
+            timeouts.get("connect"),
+            Some(&Value::Duration(Duration::from_secs(30)))
+        );
+        assert_eq!(
+            timeouts.get("session").and_then(Value::as_duration),
+            Some(Duration::from_secs(90 * 60))
+        );
+        assert_eq!(
+            timeouts.get("poll").and_then(Value::as_duration),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            timeouts.get("mixed").and_then(Value::as_duration),
+            Some(Duration::new(93_784, 5_006_007))
+        );
+        assert_eq!(Value::Integer(30).as_duration(), None);
+    }
+
+    #[test]
+    fn test_invalid_durations() {
+        let err = ThethaCoreConfig::parse("<t>\na == 30x").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 6: Unable to parse value '30x'"
+        );
+        for value in [
+            "s", "1.5s", "-5s", "1h 30m", "1h1h", "30s1h", "1ms1s", "5ns5ns", "1m1h",
+        ] {
+            let input = format!("<t>\na == {}", value);
+            assert!(
+                ThethaCoreConfig::parse(&input).is_err(),
+                "{} should not parse",
+                value
+            );
+        }
+
+        let err = ThethaCoreConfig::parse("<t>\na == 99999999999999999999d").unwrap_err();
+        assert!(err.to_string().contains("Duration out of range"));
+    }
+
+    #[test]
+    fn test_byte_sizes() {
+        let input = r#"
+        <limits>
+        upload == 10MB
+        buffer == 512KiB
+        page == 4096B
+        disk == 2GiB
+        count == 10
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let limits = config.sections.get("limits").unwrap();
+        assert_eq!(limits.get("upload"), Some(&Value::Bytes(10_000_000)));
+        assert_eq!(
+            limits.get("buffer").and_then(Value::as_bytes),
+            Some(512 * 1024)
+        );
+        assert_eq!(limits.get("page").and_then(Value::as_bytes), Some(4096));
+        assert_eq!(limits.get("disk").and_then(Value::as_bytes), Some(2 << 30));
+        assert_eq!(limits.get("count"), Some(&Value::Integer(10)));
+        assert_eq!(Value::Integer(10).as_bytes(), None);
+    }
+
+    #[test]
+    fn test_byte_sizes_are_case_sensitive() {
+        for value in ["10mb", "10Mb", "512kib", "512KIB", "1.5MB", "-1KB"] {
+            let input = format!("<limits>\na == {}", value);
+            assert!(
+                ThethaCoreConfig::parse(&input).is_err(),
+                "{} should not parse",
+                value
+            );
+        }
+
+        let err = ThethaCoreConfig::parse("<limits>\na == 99999999999GiB").unwrap_err();
+        assert!(err.to_string().contains("Byte size out of range"));
+    }
+
+    /// Values whose `Display` output the parser reads back unchanged.
+    fn arb_value() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            any::<String>().prop_map(Value::String),
+            any::<i64>().prop_map(Value::Integer),
+            prop::num::f64::NORMAL.prop_map(Value::Float),
+            any::<bool>().prop_map(Value::Boolean),
+            Just(Value::Null),
+            (any::<u64>(), 0..1_000_000_000u32)
+                .prop_map(|(secs, nanos)| Value::Duration(Duration::new(secs, nanos))),
+            any::<u64>().prop_map(Value::Bytes),
+        ];
+        leaf.prop_recursive(3, 32, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+                prop::collection::vec((any::<String>(), inner), 0..4)
+                    .prop_map(|pairs| Value::Object(pairs.into_iter().collect())),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_display_round_trips(value in arb_value()) {
+            let parsed = parse_value(&value.to_string(), &ParserOptions::default(), 1, 1);
+            prop_assert_eq!(parsed.unwrap(), value);
+        }
+
+        #[test]
+        fn prop_section_names_round_trip(
+            names in prop::collection::vec("[a-z<>\\\\#;*/ '\"`\\[\\]{}é]{1,8}", 1..4)
+        ) {
+            let mut config = ThethaCoreConfig::new();
+            for name in &names {
+                config.section_entry(name).insert("x".to_string(), Value::Integer(1));
+            }
+            // Names the formatter refuses are covered by its own tests.
+            prop_assume!(config.sections.keys().all(|name| is_writable_section(name)));
+            let reparsed = ThethaCoreConfig::parse(&config.to_string());
+            prop_assert_eq!(reparsed.unwrap(), config);
+        }
+
+        // The scanner replaced these regexes; it must accept exactly the same forms.
+        #[test]
+        fn prop_number_forms_match_regexes(s in "[-+0-9._eEinfatyINFANé€]{0,8}") {
+            let integer = Regex::new(r"^[+-]?\d+$").unwrap();
+            let float = Regex::new(
+                r"^[+-]?(?:(?:\d+\.?\d*|\.\d+)(?:[eE][+-]?\d+)?|(?i:inf|infinity|nan))$",
+            )
+            .unwrap();
+            prop_assert_eq!(is_integer(&s), integer.is_match(&s));
+            prop_assert_eq!(is_float(&s), float.is_match(&s));
+        }
+
+        #[test]
+        fn prop_radix_forms_match_regex(s in "[-+0xobXB1fz_ü]{0,6}") {
+            let radix = Regex::new(r"^([+-]?)0([xob])(\w*)$").unwrap();
+            prop_assert_eq!(split_radix(&s).is_some(), radix.is_match(&s));
+        }
+
+        #[test]
+        fn prop_unit_forms_match_regexes(s in "[0-9nsumhdBKMGiµ]{0,6}") {
+            let duration = Regex::new(
+                r"^(?:\d+d)?(?:\d+h)?(?:\d+m)?(?:\d+s)?(?:\d+ms)?(?:\d+us)?(?:\d+ns)?$",
+            )
+            .unwrap();
+            let bytes = Regex::new(r"^(\d+)(B|KB|MB|GB|KiB|MiB|GiB)$").unwrap();
+            prop_assert_eq!(
+                duration_components(&s).is_some(),
+                !s.is_empty() && duration.is_match(&s)
+            );
+            prop_assert_eq!(split_byte_size(&s).is_some(), bytes.is_match(&s));
+        }
+
+        // Malformed input, including multi-byte characters next to delimiters,
+        // must give an error rather than panic.
+        #[test]
+        fn prop_scanner_never_panics(s in "[\\[\\]{}\"'`,= #;/\n\\\\a1é€ü🙂]{0,16}") {
+            let _ = parse_value(&s, &ParserOptions::default(), 1, 1);
+            let _ = ThethaCoreConfig::parse(&format!("<a>\nx == {}", s));
+        }
+    }
+
+    #[test]
+    fn test_malformed_object_pairs() {
+        let cases = [
+            ("{ a == }", 8, "Missing value in object pair 'a =='"),
+            (
+                "{ a == 1, b == , c == 3 }",
+                16,
+                "Missing value in object pair 'b =='",
+            ),
+            ("{ == 1 }", 8, "Missing key in object pair '== 1'"),
+            ("{ a == 1, , }", 16, "Missing key in object pair ''"),
+            (
+                "{ a = 1 }",
+                8,
+                "Expected '==' between key and value in object pair 'a = 1'",
+            ),
+            (
+                "{ \"a\" 1 }",
+                8,
+                "Expected '==' between key and value in object pair '\"a\" 1'",
+            ),
+            (
+                "{ a === 1 }",
+                8,
+                "Expected '==' between key and value in object pair 'a === 1'",
+            ),
+        ];
+        for (value, column, message) in cases {
+            let input = format!("<data>\nx == {}\n", value);
+            let err = ThethaCoreConfig::parse(&input).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("❌ Syntax error on line 2, col {}: {}", column, message),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_multi_line_containers_with_comments() {
+        let input = r##"
+        <shape>
+        coords == [ # x, y, z
+            1.0,
+            # 2.0 was wrong
+            2.5, // corrected
+            ; the last one
+            3.0
+        ]
+        style == {
+            "color" == "red", # primary
+            // "width" == 2,
+            "tags" == ["a", "# not a comment"]
+        } # done
+        after == 1
+        "##;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let shape = config.sections.get("shape").unwrap();
+        assert_eq!(
+            shape.get("coords"),
+            Some(&Value::Array(vec![
+                Value::Float(1.0),
+                Value::Float(2.5),
+                Value::Float(3.0),
+            ]))
+        );
+        assert_eq!(
+            shape.get("style"),
+            Some(&Value::Object(IndexMap::from([
+                ("color".to_string(), Value::String("red".to_string())),
+                (
+                    "tags".to_string(),
+                    Value::Array(vec![
+                        Value::String("a".to_string()),
+                        Value::String("# not a comment".to_string()),
+                    ])
+                ),
+            ])))
+        );
+        assert_eq!(shape.get("after"), Some(&Value::Integer(1)));
+    }
+
+    #[test]
+    fn test_non_ascii_scalars() {
+        let err = ThethaCoreConfig::parse("<a>\nx == é").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 6: Unable to parse value 'é'"
+        );
+        let err = ThethaCoreConfig::parse("<a>\nx == naïve # comment").unwrap_err();
+        assert!(matches!(err, ParseError::InvalidValue { .. }));
+
+        let config = ThethaCoreConfig::parse("<a>\nx == `héllo\ny == \"ünï\" # ç").unwrap();
+        assert_eq!(config.get_string("a", "x"), Some("héllo"));
+        assert_eq!(config.get_string("a", "y"), Some("ünï"));
+    }
+
+    #[test]
+    fn test_multi_line_containers_with_non_ascii() {
+        let input = "<a>\nx == [ # café\n  \"é\", // ünïcode\n  `naïve, ; ç\n  \"€\"\n]\n";
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(
+            config.get_string_array("a", "x"),
+            Some(vec!["é".to_string(), "naïve".to_string(), "€".to_string()])
+        );
+
+        let input = "<a>\nx == [\n  \"é\", # ok\n  é\n]\n";
+        let err = ThethaCoreConfig::parse(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 4, col 3: Unable to parse value 'é'"
+        );
+    }
+
+    #[test]
+    fn test_multi_line_container_errors() {
+        let err = ThethaCoreConfig::parse("<s>\nx == [\n  1,\n  @bad\n]\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 4, col 3: Unable to parse value '@bad'"
+        );
+
+        let err = ThethaCoreConfig::parse("<s>\nx == { # open\n  a == 1,\ny == 2\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "❌ Syntax error on line 2, col 6: Unterminated object '{'"
+        );
+    }
+
+    #[test]
+    fn test_scanner_non_ascii_in_containers() {
+        for input in [
+            "<a>\nx == [é]",
+            "<a>\nx == {k == é}",
+            "<a>\nx == [\"é\", é]",
+        ] {
+            let result = std::panic::catch_unwind(|| ThethaCoreConfig::parse(input));
+            assert!(
+                matches!(result, Ok(Err(ParseError::InvalidValue { .. }))),
+                "{:?}",
+                input
+            );
+        }
+        let config = ThethaCoreConfig::parse("<a>\nx == [\"é\", `ü, { \"ç\" == \"€\" }]").unwrap();
+        assert_eq!(
+            config.get_value("a", "x").unwrap().to_string(),
+            r#"["é", "ü", { "ç" == "€" }]"#
+        );
+    }
+
+    #[test]
+    fn test_scanner_rejects_malformed_containers() {
+        let cases = [
+            ("[1, 2", "Unterminated array '[1, 2'"),
+            ("{ a == 1", "Unterminated object '{ a == 1'"),
+            ("\"abc", "Unterminated string '\"abc'"),
+            ("[\"a\" \"b\"]", "Unexpected characters after value '\"b\"'"),
+            ("[1] 2", "Unexpected characters after value '2'"),
+            (
+                "{ a == 1 == 2 }",
+                "Expected ',' or '}' after object pair 'a == 1 == 2'",
+            ),
+            ("'it's'", "Unexpected characters after value 's''"),
+        ];
+        for (value, message) in cases {
+            let input = format!("<data>\nx == {}\n", value);
+            let err = ThethaCoreConfig::parse(&input).unwrap_err();
+            assert!(err.to_string().ends_with(message), "{}: got {}", value, err);
+        }
+    }
 }
@@ -0,0 +1,307 @@
+use crate::errors::ValidationError;
+use crate::parser::{ThethaCoreConfig, Value};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// The variant a [`Schema`] expects a value to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Null,
+    Array,
+    Object,
+    Duration,
+    Bytes,
+    #[cfg(feature = "chrono")]
+    DateTime,
+}
+
+impl ValueKind {
+    /// The kind of `value`.
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::String(_) => ValueKind::String,
+            Value::Integer(_) => ValueKind::Integer,
+            Value::Float(_) => ValueKind::Float,
+            Value::Boolean(_) => ValueKind::Boolean,
+            Value::Null => ValueKind::Null,
+            Value::Array(_) => ValueKind::Array,
+            Value::Object(_) => ValueKind::Object,
+            Value::Duration(_) => ValueKind::Duration,
+            Value::Bytes(_) => ValueKind::Bytes,
+            #[cfg(feature = "chrono")]
+            Value::DateTime(_) => ValueKind::DateTime,
+        }
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ValueKind::String => "string",
+            ValueKind::Integer => "integer",
+            ValueKind::Float => "float",
+            ValueKind::Boolean => "boolean",
+            ValueKind::Null => "null",
+            ValueKind::Array => "array",
+            ValueKind::Object => "object",
+            ValueKind::Duration => "duration",
+            ValueKind::Bytes => "bytes",
+            #[cfg(feature = "chrono")]
+            ValueKind::DateTime => "datetime",
+        })
+    }
+}
+
+/// A check on a single `(section, key)` entry.
+#[derive(Debug, Clone)]
+enum Rule {
+    Kind { kind: ValueKind, required: bool },
+    Range(RangeInclusive<f64>),
+    ArrayOf(ValueKind),
+}
+
+/// Expectations a configuration is checked against with
+/// [`ThethaCoreConfig::validate`].
+///
+/// Built up with chained calls, e.g.
+/// `Schema::new().require("general", "app_name", ValueKind::String)`.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    rules: Vec<(String, String, Rule)>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require `section` to contain `key`, holding a value of `kind`.
+    pub fn require(self, section: &str, key: &str, kind: ValueKind) -> Self {
+        self.rule(
+            section,
+            key,
+            Rule::Kind {
+                kind,
+                required: true,
+            },
+        )
+    }
+
+    /// Allow `key` to be missing from `section`, but if present it must hold
+    /// a value of `kind`.
+    pub fn optional(self, section: &str, key: &str, kind: ValueKind) -> Self {
+        self.rule(
+            section,
+            key,
+            Rule::Kind {
+                kind,
+                required: false,
+            },
+        )
+    }
+
+    /// Require an integer or float under `key` to lie within `range`. Missing
+    /// keys and other kinds of value are left to the other rules.
+    pub fn range(self, section: &str, key: &str, range: RangeInclusive<f64>) -> Self {
+        self.rule(section, key, Rule::Range(range))
+    }
+
+    /// Require every element of an array under `key` to be a value of `kind`.
+    /// Missing keys and values that aren't arrays are left to the other rules.
+    pub fn array_of(self, section: &str, key: &str, kind: ValueKind) -> Self {
+        self.rule(section, key, Rule::ArrayOf(kind))
+    }
+
+    fn rule(mut self, section: &str, key: &str, rule: Rule) -> Self {
+        self.rules
+            .push((section.to_string(), key.to_string(), rule));
+        self
+    }
+}
+
+impl ThethaCoreConfig {
+    /// Check the configuration against `schema`.
+    ///
+    /// Every rule is checked, so the error lists all problems at once, in the
+    /// order the rules were added.
+    pub fn validate(&self, schema: &Schema) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        for (section, key, rule) in &schema.rules {
+            let value = self.get_value(section, key);
+            match (rule, value) {
+                (Rule::Kind { required: true, .. }, None) => {
+                    errors.push(ValidationError::Missing {
+                        section: section.clone(),
+                        key: key.clone(),
+                    });
+                }
+                (Rule::Kind { kind, .. }, Some(value)) if ValueKind::of(value) != *kind => {
+                    errors.push(ValidationError::WrongKind {
+                        section: section.clone(),
+                        key: key.clone(),
+                        expected: *kind,
+                        found: ValueKind::of(value),
+                    });
+                }
+                (Rule::Range(range), Some(value)) => {
+                    let number = match value {
+                        Value::Integer(n) => *n as f64,
+                        Value::Float(n) => *n,
+                        _ => continue,
+                    };
+                    if !range.contains(&number) {
+                        errors.push(ValidationError::OutOfRange {
+                            section: section.clone(),
+                            key: key.clone(),
+                            value: number,
+                            range: range.clone(),
+                        });
+                    }
+                }
+                (Rule::ArrayOf(kind), Some(Value::Array(items))) => {
+                    let mismatch = items
+                        .iter()
+                        .enumerate()
+                        .find(|(_, item)| ValueKind::of(item) != *kind);
+                    if let Some((index, item)) = mismatch {
+                        errors.push(ValidationError::WrongElementKind {
+                            section: section.clone(),
+                            key: key.clone(),
+                            index,
+                            expected: *kind,
+                            found: ValueKind::of(item),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::new()
+            .require("general", "app_name", ValueKind::String)
+            .optional("general", "debug", ValueKind::Boolean)
+            .require("database", "pool_size", ValueKind::Integer)
+            .range("database", "pool_size", 1.0..=100.0)
+    }
+
+    #[test]
+    fn test_validate_ok() {
+        let input = r#"
+        <general>
+        app_name == "TestApp"
+
+        <database>
+        pool_size == 10
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        assert_eq!(config.validate(&schema()), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_collects_all_errors() {
+        let input = r#"
+        <general>
+        debug == "yes"
+
+        <database>
+        pool_size == 500
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let errors = config.validate(&schema()).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::Missing {
+                    section: "general".to_string(),
+                    key: "app_name".to_string(),
+                },
+                ValidationError::WrongKind {
+                    section: "general".to_string(),
+                    key: "debug".to_string(),
+                    expected: ValueKind::Boolean,
+                    found: ValueKind::String,
+                },
+                ValidationError::OutOfRange {
+                    section: "database".to_string(),
+                    key: "pool_size".to_string(),
+                    value: 500.0,
+                    range: 1.0..=100.0,
+                },
+            ]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "❌ Error: Missing required key 'app_name' in section 'general'"
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            "❌ Error: Expected boolean for 'general/debug', found string"
+        );
+        assert_eq!(
+            errors[2].to_string(),
+            "❌ Error: Value 500 of 'database/pool_size' is outside 1..=100"
+        );
+    }
+
+    #[test]
+    fn test_validate_wrong_kind_skips_range() {
+        let config = ThethaCoreConfig::parse("<database>\npool_size == \"ten\"").unwrap();
+        let schema = Schema::new()
+            .require("database", "pool_size", ValueKind::Integer)
+            .range("database", "pool_size", 1.0..=100.0);
+        let errors = config.validate(&schema).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], ValidationError::WrongKind { .. }));
+    }
+
+    #[test]
+    fn test_validate_array_of() {
+        let input = r#"
+        <shape>
+        coords == [1.0, 2.0, 3.0]
+        mixed == [1.0, 2, "three"]
+        empty == []
+        name == "square"
+        "#;
+        let config = ThethaCoreConfig::parse(input).unwrap();
+        let schema = Schema::new()
+            .array_of("shape", "coords", ValueKind::Float)
+            .array_of("shape", "empty", ValueKind::Float)
+            .array_of("shape", "name", ValueKind::Float)
+            .array_of("shape", "missing", ValueKind::Float);
+        assert_eq!(config.validate(&schema), Ok(()));
+
+        let schema = Schema::new().array_of("shape", "mixed", ValueKind::Float);
+        let errors = config.validate(&schema).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![ValidationError::WrongElementKind {
+                section: "shape".to_string(),
+                key: "mixed".to_string(),
+                index: 1,
+                expected: ValueKind::Float,
+                found: ValueKind::Integer,
+            }]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "❌ Error: Expected float at index 1 of 'shape/mixed', found integer"
+        );
+    }
+}